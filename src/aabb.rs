@@ -0,0 +1,88 @@
+#![allow(dead_code)]
+
+use crate::{interval::Interval, ray::Ray, vec3::Point3};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    /// Builds the box spanned by two opposite corners, in any order.
+    pub fn new(a: Point3, b: Point3) -> Self {
+        return Self {
+            min: Point3::from_floats(a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+            max: Point3::from_floats(a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+        };
+    }
+
+    /// Contains nothing, grows to whatever is merged into it.
+    pub fn empty() -> Self {
+        return Self {
+            min: Point3::from_floats(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point3::from_floats(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        };
+    }
+
+    pub fn grow(&mut self, p: &Point3) {
+        self.min = Point3::from_floats(
+            self.min.0.min(p.0),
+            self.min.1.min(p.1),
+            self.min.2.min(p.2),
+        );
+        self.max = Point3::from_floats(
+            self.max.0.max(p.0),
+            self.max.1.max(p.1),
+            self.max.2.max(p.2),
+        );
+    }
+
+    pub fn merge(&mut self, other: &Aabb) {
+        self.grow(&other.min);
+        self.grow(&other.max);
+    }
+
+    pub fn center(&self) -> Point3 {
+        return (self.min + self.max).mul(0.5);
+    }
+
+    pub fn extent(&self) -> Point3 {
+        return self.max - self.min;
+    }
+
+    /// Slab test. Returns the parametric range `(t_enter, t_exit)` of the ray
+    /// inside the box, clipped to `interval`.
+    pub fn hit_range(&self, r: &Ray, interval: &Interval) -> Option<(f64, f64)> {
+        let mut t_min = interval.min;
+        let mut t_max = interval.max;
+
+        let axes = [
+            (r.origin.0, r.direction.0, self.min.0, self.max.0),
+            (r.origin.1, r.direction.1, self.min.1, self.max.1),
+            (r.origin.2, r.direction.2, self.min.2, self.max.2),
+        ];
+
+        for (origin, dir, min, max) in axes {
+            let inv = 1. / dir;
+            let mut t0 = (min - origin) * inv;
+            let mut t1 = (max - origin) * inv;
+            if inv < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // NaN (ray parallel to and on a slab plane) leaves the bounds untouched
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max < t_min {
+                return None;
+            }
+        }
+
+        return Some((t_min, t_max));
+    }
+}
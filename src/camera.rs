@@ -1,19 +1,84 @@
 use std::{
-    fs,
-    sync::{Arc, Mutex},
-    time::Instant,
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use rayon::iter::{ParallelBridge, ParallelIterator};
-
 use crate::{
+    checkpoint::{self, Checkpoint},
+    film::Film,
+    filter::{Filter, FilterSampler},
+    hit::Hit,
     interval::Interval,
+    material::{self, Scatter},
+    progress::{self, Progress, RenderStats},
     ray::Ray,
-    utils,
+    sampler::{self, Sampler, SamplerKind},
+    spectrum,
+    tile::{self, Tile, TileOrder},
     vec3::{Color, Point3, Vec3},
-    world::{AnyHit, World},
+    world::{self, AnyHit, World},
 };
 
+/// Spreads the sample budget, `samples_per_pixel` (or the progressive
+/// `target_samples`) on average, by noise. Every pixel first gets
+/// `min_samples`, then each pass doubles the sample count of the noisiest
+/// pixels while the budget lasts. Pixels stop once the standard error of
+/// their mean luminance, relative to that mean, drops below `threshold`, and
+/// never get more than `max_samples`, so a large budget can go unspent.
+///
+/// Counts stay powers of two below `max_samples` (`min_samples` is rounded up
+/// to one), which keeps the Sobol and Halton prefixes of every pixel well
+/// stratified. The stratified sampler is only stratified over all of
+/// `max_samples`. Passes follow the doublings, the progressive
+/// `samples_per_pass` is ignored.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveSampling {
+    pub min_samples: i32,
+    pub max_samples: i32,
+    pub threshold: f64,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        return Self {
+            min_samples: 16,
+            max_samples: 1024,
+            threshold: 0.01,
+        };
+    }
+}
+
+/// Pass size of plain renders that save checkpoints.
+const CHECKPOINT_PASS_SAMPLES: i32 = 16;
+
+/// Renders in passes over the whole image and writes the output after each
+/// one. Stops after `target_samples`, once `time_budget` ran out (checked
+/// between passes) or when the film's mean relative error drops below
+/// `noise_threshold`, whatever comes first.
+#[derive(Clone, Copy, Debug)]
+pub struct Progressive {
+    pub samples_per_pass: i32,
+    pub target_samples: i32,
+    pub time_budget: Option<Duration>,
+    pub noise_threshold: Option<f64>,
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        return Self {
+            samples_per_pass: 4,
+            target_samples: 1024,
+            time_budget: None,
+            noise_threshold: None,
+        };
+    }
+}
+
 pub struct Camera {
     pub aspect_ratio: f64,
     pub img_width: i32,
@@ -25,7 +90,24 @@ pub struct Camera {
     pub vup: Point3,
     pub defocus_angle: f64,
     pub focus_dist: f64,
-    pixel_samples_scale: f64,
+    /// Trace wavelengths instead of RGB, needed for dispersion
+    pub spectral: bool,
+    /// How pixel, lens and bounce samples are distributed
+    pub sampler: SamplerKind,
+    /// Same seed, same image, regardless of how rows end up on threads
+    pub seed: u64,
+    /// Spreads `samples_per_pixel` over the image by per pixel noise
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Replaces the single pass of `samples_per_pixel` with passes and snapshots
+    pub progressive: Option<Progressive>,
+    pub output_path: PathBuf,
+    pub checkpoint: Option<Checkpoint>,
+    /// Pixel reconstruction filter
+    pub filter: Filter,
+    /// Edge length of the square tiles handed to worker threads, in pixels
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    filter_sampler: FilterSampler,
     img_height: i32,
     center: Point3,
     first_pixel: Point3,
@@ -39,57 +121,326 @@ pub struct Camera {
 }
 
 impl Camera {
-    pub fn render(&mut self, world: Arc<World>) {
+    #[allow(dead_code)]
+    pub fn render(&mut self, world: Arc<World>) -> io::Result<RenderStats> {
+        return self.render_with_progress(world, |_| {});
+    }
+
+    /// Like `render`, calling `on_progress` from the worker threads whenever
+    /// a tile is done.
+    pub fn render_with_progress(
+        &mut self,
+        world: Arc<World>,
+        on_progress: impl Fn(&Progress) + Sync,
+    ) -> io::Result<RenderStats> {
         self.init();
 
-        let headers = format!("P3\n{} {}\n255\n", self.img_width, self.img_height);
-        let buf = Arc::new(Mutex::new(Vec::<String>::with_capacity(
-            self.img_width as usize * self.img_height as usize,
-        )));
+        let width = self.img_width as usize;
+        let height = self.img_height as usize;
+        let hash = self
+            .checkpoint
+            .as_ref()
+            .map(|_| self.settings_hash(world::scene_hash(&world)));
 
-        let mut buf_lock = buf.lock().unwrap();
-        buf_lock.resize_with(
-            self.img_width as usize * self.img_height as usize,
-            Default::default,
-        );
-        drop(buf_lock);
+        let resumed = match (&self.checkpoint, hash) {
+            (Some(c), Some(hash)) if c.resume && c.path.exists() => {
+                let film = checkpoint::load(&c.path, hash)?;
+                if film.is_none() {
+                    eprintln!(
+                        "{} is from a different scene or camera settings, starting over",
+                        c.path.display()
+                    );
+                }
+                film
+            }
+            _ => None,
+        };
+        let mut film = resumed.unwrap_or_else(|| Film::new(width, height));
+
+        // A plain render is one pass, unless it needs to stop for checkpoints
+        let (passes, snapshots) = match self.progressive {
+            Some(p) => (p, true),
+            None => {
+                let per_pass = match self.checkpoint {
+                    Some(_) => CHECKPOINT_PASS_SAMPLES.min(self.samples_per_pixel),
+                    None => self.samples_per_pixel,
+                };
+                let passes = Progressive {
+                    samples_per_pass: per_pass,
+                    target_samples: self.samples_per_pixel,
+                    time_budget: None,
+                    noise_threshold: None,
+                };
+                (passes, false)
+            }
+        };
+        let per_pass = passes.samples_per_pass.max(1) as u64;
+        let target = passes.target_samples.max(0) as u64;
+        let pixels_total = width * height;
 
         let start = Instant::now();
-        (0..self.img_height).par_bridge().for_each(|y| {
-            for x in 0..self.img_width {
-                let mut col = Color::default();
-                for _ in 0..self.samples_per_pixel {
-                    let r = self.get_ray(x, y);
-                    col += self.ray_color(&r, &world, 1);
+        let mut last_save = Instant::now();
+        let resumed_samples = film.total_samples();
+
+        // What the progress is measured against, an upper bound when adaptive
+        let (planned, planned_samples, sampler_samples) = match self.adaptive {
+            Some(a) => {
+                let min = a.min_samples.max(2) as u64;
+                let doublings = (a.max_samples.max(1) as u64 / min).max(1).ilog2() as usize;
+                let budget = target * pixels_total as u64;
+                (
+                    1 + doublings,
+                    budget.saturating_sub(resumed_samples),
+                    a.max_samples,
+                )
+            }
+            None => {
+                let done = film.pixels.iter().map(|p| p.count()).min().unwrap_or(0);
+                let left = target.saturating_sub(done);
+                (
+                    left.div_ceil(per_pass) as usize,
+                    left * pixels_total as u64,
+                    passes.target_samples,
+                )
+            }
+        };
+
+        let mut pass = 0;
+        let total_samples = AtomicU64::new(0);
+        let total_rays = AtomicU64::new(0);
+
+        loop {
+            let allocation = match self.adaptive {
+                Some(a) => {
+                    let spent = resumed_samples + total_samples.load(Ordering::Relaxed);
+                    let budget = target * pixels_total as u64;
+                    Self::adaptive_allocation(&a, &film, budget.saturating_sub(spent))
+                }
+                None => film
+                    .pixels
+                    .iter()
+                    .map(|p| target.saturating_sub(p.count()).min(per_pass) as u32)
+                    .collect(),
+            };
+            if allocation.iter().all(|n| *n == 0) {
+                break;
+            }
+            pass += 1;
+
+            let tiles_done = AtomicUsize::new(0);
+            let pixels_done = AtomicUsize::new(0);
+            let on_tile = |tiles_total: usize, tile: &Tile, tile_samples: u64, tile_rays: u64| {
+                let tiles_done = tiles_done.fetch_add(1, Ordering::Relaxed) + 1;
+                let pixels_done = pixels_done
+                    .fetch_add(tile.width() * tile.height(), Ordering::Relaxed)
+                    + tile.width() * tile.height();
+                let samples =
+                    total_samples.fetch_add(tile_samples, Ordering::Relaxed) + tile_samples;
+                let rays = total_rays.fetch_add(tile_rays, Ordering::Relaxed) + tile_rays;
+
+                let elapsed = start.elapsed();
+                let fraction = samples as f64 / planned_samples.max(1) as f64;
+                let mut eta =
+                    (fraction > 0.).then(|| elapsed.mul_f64((1. - fraction).max(0.) / fraction));
+                if let Some(budget) = passes.time_budget {
+                    let left = budget.saturating_sub(elapsed);
+                    eta = Some(eta.map_or(left, |eta| eta.min(left)));
+                }
+
+                on_progress(&Progress {
+                    pass,
+                    passes: planned.max(pass),
+                    tiles_done,
+                    tiles_total,
+                    pixels_done,
+                    pixels_total,
+                    samples,
+                    rays,
+                    elapsed,
+                    samples_per_second: progress::per_second(samples, elapsed),
+                    rays_per_second: progress::per_second(rays, elapsed),
+                    fraction: fraction.min(1.),
+                    eta,
+                    done: false,
+                });
+            };
+            self.render_pass(&mut film, &world, &allocation, sampler_samples, &on_tile);
+
+            if snapshots {
+                film.write_ppm(&self.output_path)?;
+            }
+
+            if let (Some(c), Some(hash)) = (&self.checkpoint, hash) {
+                if last_save.elapsed() >= c.interval {
+                    checkpoint::save(&c.path, hash, &film)?;
+                    last_save = Instant::now();
                 }
+            }
 
-                utils::write_color(
-                    Arc::clone(&buf),
-                    &col.mul(self.pixel_samples_scale),
-                    x as usize,
-                    y as usize,
-                    self.img_width as usize,
-                );
+            let out_of_time = passes.time_budget.is_some_and(|t| start.elapsed() >= t);
+            let clean = passes
+                .noise_threshold
+                .is_some_and(|t| film.mean_error() < t);
+            if out_of_time || clean {
+                break;
             }
+        }
+
+        let elapsed = start.elapsed();
+
+        // The final state too, a later run can resume it with more samples
+        if let (Some(c), Some(hash)) = (&self.checkpoint, hash) {
+            checkpoint::save(&c.path, hash, &film)?;
+        }
+        film.write_ppm(&self.output_path)?;
+
+        // Stopping early never gets the last tile of the planned passes
+        let (samples, rays) = (total_samples.into_inner(), total_rays.into_inner());
+        let tiles_total = tile::tiles(width, height, self.tile_size, self.tile_order).len();
+        on_progress(&Progress {
+            pass,
+            passes: pass,
+            tiles_done: tiles_total,
+            tiles_total,
+            pixels_done: pixels_total,
+            pixels_total,
+            samples,
+            rays,
+            elapsed,
+            samples_per_second: progress::per_second(samples, elapsed),
+            rays_per_second: progress::per_second(rays, elapsed),
+            fraction: 1.,
+            eta: Some(Duration::ZERO),
+            done: true,
         });
 
-        println!("\nRT took: {:?}", start.elapsed());
+        return Ok(RenderStats {
+            width,
+            height,
+            elapsed,
+            passes: pass,
+            samples,
+            rays,
+            resumed_samples,
+            average_samples_per_pixel: film.total_samples() as f64 / (width * height) as f64,
+            mean_error: film.mean_error(),
+        });
+    }
+
+    /// Everything that changes what a sample of a pixel looks like, mixed
+    /// with the hash of the scene. Sample counts are left out so resumed renders can
+    /// go on longer.
+    fn settings_hash(&self, scene_hash: u64) -> u64 {
+        return sampler::hash(
+            &[
+                &[
+                    scene_hash,
+                    self.img_width as u64,
+                    self.img_height as u64,
+                    self.vertical_fov.to_bits(),
+                    self.defocus_angle.to_bits(),
+                    self.focus_dist.to_bits(),
+                    self.max_bounces_per_ray as u64,
+                    self.spectral as u64,
+                    self.sampler as u64,
+                    self.seed,
+                ][..],
+                &self.look_from.to_bits(),
+                &self.look_at.to_bits(),
+                &self.vup.to_bits(),
+                &self.filter.to_bits(),
+            ]
+            .concat(),
+        );
+    }
+
+    /// Samples every pixel gets in the next adaptive pass, all zero once
+    /// there is nothing left to do. See `AdaptiveSampling`.
+    fn adaptive_allocation(a: &AdaptiveSampling, film: &Film, mut budget: u64) -> Vec<u32> {
+        let max_samples = a.max_samples.max(1) as u64;
+        let min_samples = (a.min_samples.max(2) as u64)
+            .next_power_of_two()
+            .min(max_samples);
 
-        let buf_lock = buf.lock().unwrap();
+        // Everybody needs a few samples before their error means anything
+        if film.pixels.iter().any(|p| p.count() < min_samples) {
+            return film
+                .pixels
+                .iter()
+                .map(|p| min_samples.saturating_sub(p.count()) as u32)
+                .collect();
+        }
+
+        let mut noisy: Vec<(usize, f64)> = film
+            .pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.count() < max_samples)
+            .map(|(i, p)| (i, p.relative_error()))
+            .filter(|(_, error)| *error >= a.threshold)
+            .collect();
+        noisy.sort_by(|a, b| b.1.total_cmp(&a.1));
 
-        let out = headers
-            .chars()
-            .chain(buf_lock.join("\n").chars())
-            .collect::<String>();
+        let mut allocation = vec![0; film.pixels.len()];
+        for (i, _) in noisy {
+            let count = film.pixels[i].count();
+            let extra = count.min(max_samples - count);
+            if extra <= budget {
+                allocation[i] = extra as u32;
+                budget -= extra;
+            }
+        }
+        return allocation;
+    }
 
-        drop(buf_lock);
+    /// Adds `allocation[i]` samples to pixel `i`. Sample indices continue
+    /// from what the film already has, `total_samples` is what the sampler
+    /// should expect per pixel over the whole render. `on_tile` gets the tile
+    /// count, each finished tile and the samples and rays spent on it.
+    fn render_pass(
+        &self,
+        film: &mut Film,
+        world: &World,
+        allocation: &[u32],
+        total_samples: i32,
+        on_tile: &(dyn Fn(usize, &Tile, u64, u64) + Sync),
+    ) {
+        let width = film.width;
+        let tiles = tile::tiles(film.width, film.height, self.tile_size, self.tile_order);
+        let next = AtomicUsize::new(0);
+        let film = Mutex::new(film);
 
-        fs::write("test-img.ppm", out.as_bytes()).expect("Unable to write to file");
+        // Every worker pulls the next tile in order, so they come in roughly
+        // the order asked for even though any worker can take any tile.
+        rayon::broadcast(|_| {
+            let mut sampler = self.sampler.create(total_samples as usize, self.seed);
+            while let Some(tile) = tiles.get(next.fetch_add(1, Ordering::Relaxed)) {
+                let mut buffer = film.lock().unwrap().read_tile(tile);
+                let (mut tile_samples, mut tile_rays) = (0, 0);
+                for (i, pixel) in buffer.iter_mut().enumerate() {
+                    let x = tile.x0 + i % tile.width();
+                    let y = tile.y0 + i / tile.width();
+                    for _ in 0..allocation[y * width + x] {
+                        sampler.start_pixel_sample(x, y, pixel.count() as usize);
+                        let (r, weight) = self.get_ray(x as i32, y as i32, sampler.as_mut());
+                        let sample = if self.spectral {
+                            self.spectral_color(r, world, sampler.as_mut(), &mut tile_rays)
+                        } else {
+                            self.ray_color(&r, world, 1, sampler.as_mut(), &mut tile_rays)
+                        };
+                        pixel.add(&sample, weight);
+                        tile_samples += 1;
+                    }
+                }
+                film.lock().unwrap().write_tile(tile, &buffer);
+                on_tile(tiles.len(), tile, tile_samples, tile_rays);
+            }
+        });
     }
 
     fn init(&mut self) {
+        self.filter_sampler = FilterSampler::new(&self.filter);
         self.img_height = ((self.img_width as f64 / self.aspect_ratio) as i32).max(1);
-        self.pixel_samples_scale = 1. / self.samples_per_pixel as f64;
 
         self.center = self.look_from;
 
@@ -123,50 +474,142 @@ impl Camera {
         self.defocus_disk_y = self.y.mul(defocus_radius);
     }
 
-    fn ray_color(&self, r: &Ray, world: &World, depth: i32) -> Color {
+    /// Adds every ray it intersects with the scene to `rays`.
+    fn ray_color(
+        &self,
+        r: &Ray,
+        world: &World,
+        depth: i32,
+        sampler: &mut dyn Sampler,
+        rays: &mut u64,
+    ) -> Color {
         if depth >= self.max_bounces_per_ray {
             return Color::default();
         }
 
-        if let Some(hit) = world.any_hit(r, Interval::new(0.001, f64::INFINITY)) {
+        *rays += 1;
+        if let Some(mut hit) = world.any_hit(r, Interval::new(0.001, f64::INFINITY)) {
+            hit.sample = sampler.get_bounce();
             if let Some(mat) = &hit.material {
                 if let Some(scat) = mat.scatter(r, &hit) {
-                    return scat.attenuation * self.ray_color(&scat.ray, world, depth + 1);
+                    let scat = Self::through_medium(r, &hit, scat);
+                    return scat.attenuation
+                        * self.ray_color(&scat.ray, world, depth + 1, sampler, rays);
                 }
             }
             return Color::from_floats(0., 0., 0.);
         }
 
+        return Self::background(r);
+    }
+
+    /// Folds the absorption along the segment `r` traveled to `hit` into
+    /// `scat`, and sets the medium the scattered ray continues in. Rays keep
+    /// their medium unless they cross a surface with an absorbing interior.
+    /// Leaving one always goes back to no medium, a medium nested inside
+    /// another loses the outer one.
+    fn through_medium(r: &Ray, hit: &Hit, mut scat: Scatter) -> Scatter {
+        if let Some(absorption) = &r.medium {
+            let distance = hit.t * r.direction.len();
+            scat.attenuation *= material::beer_lambert(absorption, distance);
+        }
+
+        let crossed = scat.ray.direction.dot(&hit.normal) < 0.;
+        scat.ray.medium = match scat.interior {
+            Some(absorption) if crossed => hit.front_face.then_some(absorption),
+            _ => r.medium,
+        };
+        return scat;
+    }
+
+    fn background(r: &Ray) -> Color {
         let unit_dir = r.direction.norm();
         let a = (unit_dir.y() + 1.) * 0.5;
         return Color::from_floats(1., 1., 1.).mul(1. - a)
             + Color::from_floats(0.5, 0.7, 1.).mul(a);
     }
 
-    fn get_ray(&self, x: i32, y: i32) -> Ray {
-        let offset = Self::sample_square();
+    /// Traces one path carrying several wavelengths (hero wavelength sampling).
+    /// RGB attenuations and the background are upsampled at every wavelength.
+    /// Counts its rays like `ray_color`.
+    fn spectral_color(
+        &self,
+        mut r: Ray,
+        world: &World,
+        sampler: &mut dyn Sampler,
+        rays: &mut u64,
+    ) -> Color {
+        let lambdas = spectrum::sample_wavelengths(sampler.get_1d());
+        let mut throughput = [1.; spectrum::SAMPLES];
+        let mut single_wavelength = false;
+        r.wavelength = Some(lambdas[0]);
+
+        for _ in 1..self.max_bounces_per_ray {
+            *rays += 1;
+            let Some(mut hit) = world.any_hit(&r, Interval::new(0.001, f64::INFINITY)) else {
+                let background = Self::background(&r);
+                for (t, lambda) in throughput.iter_mut().zip(&lambdas) {
+                    *t *= spectrum::rgb_to_spectral(&background, *lambda);
+                }
+                return spectrum::to_rgb(&lambdas, &throughput);
+            };
+
+            hit.sample = sampler.get_bounce();
+            let Some(mat) = &hit.material else {
+                break;
+            };
+            let Some(scat) = mat.scatter(&r, &hit) else {
+                break;
+            };
+            let scat = Self::through_medium(&r, &hit, scat);
+
+            // Only the hero wavelength took this path, the estimate stays
+            // unbiased by giving it the weight of all of them.
+            if mat.is_wavelength_dependent() && !single_wavelength {
+                single_wavelength = true;
+                throughput[0] *= spectrum::SAMPLES as f64;
+                throughput[1..].fill(0.);
+            }
+
+            for (t, lambda) in throughput.iter_mut().zip(&lambdas) {
+                *t *= spectrum::rgb_to_spectral(&scat.attenuation, *lambda);
+            }
+
+            r = scat.ray;
+            r.wavelength = Some(lambdas[0]);
+        }
+
+        return Color::default();
+    }
+
+    /// Ray through a filter importance sampled position around the pixel
+    /// center, along with the filter's sign there.
+    fn get_ray(&self, x: i32, y: i32, sampler: &mut dyn Sampler) -> (Ray, f64) {
+        let u = sampler.get_2d();
+        let (offset_x, sign_x) = self.filter_sampler.sample(u.0);
+        let (offset_y, sign_y) = self.filter_sampler.sample(u.1);
         let pixel_sample = self.first_pixel
-            + (self.pixel_delta_x.mul(x as f64 + offset.x()))
-            + (self.pixel_delta_y.mul(y as f64 + offset.y()));
+            + (self.pixel_delta_x.mul(x as f64 + offset_x))
+            + (self.pixel_delta_y.mul(y as f64 + offset_y));
+
+        let lens = sampler.get_2d();
+        // Reserved for the shutter time, keeps later dimensions in place
+        let _time = sampler.get_1d();
 
         let ray_origin = if self.defocus_angle <= 0. {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(lens)
         };
         let ray_dir = pixel_sample - ray_origin;
 
-        return Ray::new(ray_origin, ray_dir);
+        return (Ray::new(ray_origin, ray_dir), sign_x * sign_y);
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
-        let p = Vec3::random_in_unit_disk();
+    fn defocus_disk_sample(&self, u: (f64, f64)) -> Point3 {
+        let p = sampler::concentric_disk(u);
         return self.center + (self.defocus_disk_x.mul(p.0)) + (self.defocus_disk_y.mul(p.1));
     }
-
-    fn sample_square() -> Vec3 {
-        return Vec3(utils::rand_float() - 0.5, utils::rand_float() - 0.5, 0.);
-    }
 }
 
 impl Default for Camera {
@@ -180,9 +623,19 @@ impl Default for Camera {
             look_at: Vec3::from_floats(0., 0., -1.),
             vup: Vec3::from_floats(0., 1., 0.),
             vertical_fov: 90.,
-            pixel_samples_scale: 1.,
             defocus_angle: 0.,
             focus_dist: 10.,
+            spectral: false,
+            sampler: SamplerKind::Sobol,
+            seed: 0,
+            adaptive: None,
+            progressive: None,
+            output_path: PathBuf::from("test-img.ppm"),
+            checkpoint: None,
+            filter: Filter::default(),
+            tile_size: 32,
+            tile_order: TileOrder::Hilbert,
+            filter_sampler: FilterSampler::new(&Filter::default()),
             img_height: 0,
             center: Point3::default(),
             first_pixel: Point3::default(),
@@ -196,3 +649,83 @@ impl Default for Camera {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        material::{dialectric::Dialectric, lambertian::Lambertian, metal::Metal},
+        sphere::Sphere,
+    };
+
+    fn scene() -> World {
+        return vec![
+            Arc::new(Sphere::new(
+                Point3::from_floats(0., -100.5, -1.),
+                100.,
+                Arc::new(Lambertian::new(Color::from_floats(0.8, 0.8, 0.))),
+            )),
+            Arc::new(Sphere::new(
+                Point3::from_floats(0., 0., -1.2),
+                0.5,
+                Arc::new(Lambertian::new(Color::from_floats(0.1, 0.2, 0.5))),
+            )),
+            Arc::new(Sphere::new(
+                Point3::from_floats(-1., 0., -1.),
+                0.5,
+                Arc::new(Dialectric::new(1.5)),
+            )),
+            Arc::new(Sphere::new(
+                Point3::from_floats(1., 0., -1.),
+                0.5,
+                Arc::new(Metal::from_albedo(Color::from_floats(0.8, 0.6, 0.2), 0.3)),
+            )),
+        ];
+    }
+
+    fn render_film(threads: usize) -> Film {
+        let mut cam = Camera {
+            img_width: 48,
+            aspect_ratio: 4. / 3.,
+            tile_size: 8,
+            ..Default::default()
+        };
+        cam.init();
+
+        let world = scene();
+        let mut film = Film::new(cam.img_width as usize, cam.img_height as usize);
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| {
+                let allocation = vec![8; film.pixels.len()];
+                cam.render_pass(&mut film, &world, &allocation, 8, &|_, _, _, _| {});
+            });
+        return film;
+    }
+
+    #[test]
+    fn render_is_independent_of_thread_count() {
+        let bits = |film: &Film| {
+            film.pixels
+                .iter()
+                .flat_map(|p| {
+                    [
+                        p.sum.0.to_bits(),
+                        p.sum.1.to_bits(),
+                        p.sum.2.to_bits(),
+                        p.weight_sum.to_bits(),
+                        p.luminance.count,
+                        p.luminance.mean.to_bits(),
+                        p.luminance.m2.to_bits(),
+                    ]
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let single = bits(&render_film(1));
+        assert!(single.iter().any(|b| *b != 0));
+        assert_eq!(single, bits(&render_film(4)));
+    }
+}
@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    film::{Film, FilmPixel, Welford},
+    vec3::Color,
+};
+
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 2;
+const HEADER_SIZE: usize = 4 + 4 + 8 + 4 + 4;
+const PIXEL_SIZE: usize = 7 * 8;
+
+/// Periodically saves the film so long renders survive crashes.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    /// Minimum time between saves, they only happen between passes
+    pub interval: Duration,
+    /// Continue from `path` if it exists. When the scene or the camera
+    /// settings changed since (see `world::scene_hash`) the render starts
+    /// over and replaces it.
+    pub resume: bool,
+}
+
+impl Checkpoint {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        return Self {
+            path: path.into(),
+            interval: Duration::from_secs(60),
+            resume: true,
+        };
+    }
+}
+
+/// Little endian: magic, version, hash, width, height, then per pixel the
+/// summed color, summed filter weight, sample count and luminance statistics.
+pub fn save(path: &Path, hash: u64, film: &Film) -> io::Result<()> {
+    let mut out = Vec::with_capacity(HEADER_SIZE + film.pixels.len() * PIXEL_SIZE);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&hash.to_le_bytes());
+    out.extend_from_slice(&(film.width as u32).to_le_bytes());
+    out.extend_from_slice(&(film.height as u32).to_le_bytes());
+
+    for p in &film.pixels {
+        for v in [p.sum.0, p.sum.1, p.sum.2, p.weight_sum] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.extend_from_slice(&p.luminance.count.to_le_bytes());
+        out.extend_from_slice(&p.luminance.mean.to_le_bytes());
+        out.extend_from_slice(&p.luminance.m2.to_le_bytes());
+    }
+
+    // Never leave a truncated checkpoint behind if we crash while writing
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, out)?;
+    return fs::rename(tmp, path);
+}
+
+/// The saved film, or `None` if it was rendered with a different `hash`.
+pub fn load(path: &Path, hash: u64) -> io::Result<Option<Film>> {
+    let bytes = fs::read(path)?;
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+        return Err(invalid("Not a checkpoint file"));
+    }
+
+    let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+    let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+    let f64_at = |i: usize| f64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+
+    if u32_at(4) != VERSION {
+        return Err(invalid("Unsupported checkpoint version"));
+    }
+    if u64_at(8) != hash {
+        return Ok(None);
+    }
+
+    let width = u32_at(16) as usize;
+    let height = u32_at(20) as usize;
+    let size = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(PIXEL_SIZE))
+        .and_then(|n| n.checked_add(HEADER_SIZE));
+    if size != Some(bytes.len()) {
+        return Err(invalid("Checkpoint data doesn't match its dimensions"));
+    }
+
+    let pixels = (0..width * height)
+        .map(|i| {
+            let at = HEADER_SIZE + i * PIXEL_SIZE;
+            FilmPixel {
+                sum: Color::from_floats(f64_at(at), f64_at(at + 8), f64_at(at + 16)),
+                weight_sum: f64_at(at + 24),
+                luminance: Welford {
+                    count: u64_at(at + 32),
+                    mean: f64_at(at + 40),
+                    m2: f64_at(at + 48),
+                },
+            }
+        })
+        .collect();
+
+    return Ok(Some(Film {
+        width,
+        height,
+        pixels,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        return std::env::temp_dir().join(format!("rtiow-{}-{}.ckpt", std::process::id(), name));
+    }
+
+    fn film() -> Film {
+        let mut film = Film::new(3, 2);
+        for (i, pixel) in film.pixels.iter_mut().enumerate() {
+            for s in 0..=i {
+                pixel.add(
+                    &Color::from_floats(0.1 * s as f64, 0.5, i as f64),
+                    1. - 0.2 * s as f64,
+                );
+            }
+        }
+        return film;
+    }
+
+    #[test]
+    fn round_trip_keeps_every_pixel() {
+        let path = temp_path("round-trip");
+        let film = film();
+        save(&path, 42, &film).unwrap();
+        let loaded = load(&path, 42).unwrap().expect("hash matches");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((loaded.width, loaded.height), (film.width, film.height));
+        for (a, b) in film.pixels.iter().zip(&loaded.pixels) {
+            assert_eq!(a.sum.to_bits(), b.sum.to_bits());
+            assert_eq!(a.weight_sum.to_bits(), b.weight_sum.to_bits());
+            assert_eq!(a.luminance.count, b.luminance.count);
+            assert_eq!(a.luminance.mean.to_bits(), b.luminance.mean.to_bits());
+            assert_eq!(a.luminance.m2.to_bits(), b.luminance.m2.to_bits());
+        }
+    }
+
+    #[test]
+    fn other_hash_is_not_resumed() {
+        let path = temp_path("mismatch");
+        save(&path, 42, &film()).unwrap();
+        let loaded = load(&path, 43).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn truncated_file_is_invalid() {
+        let path = temp_path("truncated");
+        save(&path, 42, &film()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let err = load(&path, 42).err().expect("truncated checkpoint loads");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
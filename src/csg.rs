@@ -0,0 +1,132 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    hit::{Hit, Hittable},
+    interval::Interval,
+    ray::Ray,
+    sampler,
+};
+
+/// A stretch of the ray that lies inside a solid. The boundary hits carry
+/// the outward normal, face orientation is only resolved in `Hittable::hit`.
+pub struct Span {
+    pub enter: Hit,
+    pub exit: Hit,
+}
+
+/// A closed hittable that can report every interval along the (infinite)
+/// ray in which it is inside, sorted by `t` and non-overlapping.
+pub trait Solid: Hittable {
+    fn spans(&self, r: &Ray) -> Vec<Span>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// Left minus right
+    Difference,
+}
+
+impl CsgOp {
+    fn apply(&self, in_left: bool, in_right: bool) -> bool {
+        return match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        };
+    }
+}
+
+pub struct Csg {
+    pub op: CsgOp,
+    pub left: Arc<dyn Solid>,
+    pub right: Arc<dyn Solid>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Arc<dyn Solid>, right: Arc<dyn Solid>) -> Self {
+        return Self { op, left, right };
+    }
+}
+
+impl Solid for Csg {
+    fn spans(&self, r: &Ray) -> Vec<Span> {
+        // (boundary, is from left, is entering)
+        let mut events = Vec::<(Hit, bool, bool)>::new();
+        for (spans, from_left) in [(self.left.spans(r), true), (self.right.spans(r), false)] {
+            for span in spans {
+                events.push((span.enter, from_left, true));
+                events.push((span.exit, from_left, false));
+            }
+        }
+        events.sort_by(|a, b| a.0.t.total_cmp(&b.0.t));
+
+        let mut out = Vec::new();
+        let (mut in_left, mut in_right, mut inside) = (false, false, false);
+        let mut enter: Option<Hit> = None;
+
+        for (mut boundary, from_left, entering) in events {
+            if from_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+
+            let now_inside = self.op.apply(in_left, in_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            // Surfaces cut out by the right operand face into it
+            if !from_left && self.op == CsgOp::Difference {
+                boundary.normal = boundary.normal.inv();
+            }
+
+            if inside {
+                enter = Some(boundary);
+            } else if let Some(enter) = enter.take() {
+                out.push(Span {
+                    enter,
+                    exit: boundary,
+                });
+            }
+        }
+
+        return out;
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+        let boundaries = self
+            .spans(r)
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit]);
+
+        for boundary in boundaries {
+            if interval.sourrounds(boundary.t) {
+                let outward_normal = boundary.normal;
+                *hit = boundary;
+                hit.set_face_normal(r, &outward_normal);
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "csg",
+            &[
+                self.op as u64,
+                self.left.content_hash(),
+                self.right.content_hash(),
+            ],
+        );
+    }
+}
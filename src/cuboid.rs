@@ -0,0 +1,137 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    csg::{Solid, Span},
+    hit::{Hit, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    sampler,
+    vec3::{Point3, Vec3},
+};
+
+/// Axis aligned box.
+pub struct Cuboid {
+    pub bounds: Aabb,
+    pub material: Arc<dyn Material>,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: Arc<dyn Material>) -> Self {
+        return Self {
+            bounds: Aabb::new(a, b),
+            material,
+        };
+    }
+
+    /// Outward normal at `p` on the surface, plus `uv` in `[0, 1]` across the
+    /// face and `dpdu`, `dpdv` spanning it. Seen from outside, every face has
+    /// `u` going right and `v` going up (towards -z on the top and bottom).
+    fn surface(&self, p: &Point3) -> (Vec3, (f64, f64), Vec3, Vec3) {
+        let (min, extent) = (self.bounds.min, self.bounds.extent());
+        let center = min + extent.mul(0.5);
+        let local = (*p - center) / extent.mul(0.5);
+        // Fractions across the box along each axis
+        let f = (*p - min) / extent;
+
+        // The face we're on is the one the point is pushed the furthest towards
+        let (ax, ay, az) = (local.0.abs(), local.1.abs(), local.2.abs());
+        return if ax >= ay && ax >= az {
+            let s = local.0.signum();
+            let u = if s > 0. { 1. - f.2 } else { f.2 };
+            (
+                Vec3(s, 0., 0.),
+                (u, f.1),
+                Vec3(0., 0., -s * extent.2),
+                Vec3(0., extent.1, 0.),
+            )
+        } else if ay >= az {
+            let s = local.1.signum();
+            let v = if s > 0. { 1. - f.2 } else { f.2 };
+            (
+                Vec3(0., s, 0.),
+                (f.0, v),
+                Vec3(extent.0, 0., 0.),
+                Vec3(0., 0., -s * extent.2),
+            )
+        } else {
+            let s = local.2.signum();
+            let u = if s > 0. { f.0 } else { 1. - f.0 };
+            (
+                Vec3(0., 0., s),
+                (u, f.1),
+                Vec3(s * extent.0, 0., 0.),
+                Vec3(0., extent.1, 0.),
+            )
+        };
+    }
+
+    fn boundary(&self, r: &Ray, t: f64) -> Hit {
+        let p = r.at(t);
+        let (normal, (u, v), dpdu, dpdv) = self.surface(&p);
+        return Hit {
+            p,
+            normal,
+            t,
+            material: Some(Arc::clone(&self.material)),
+            u,
+            v,
+            dpdu,
+            dpdv,
+            ..Default::default()
+        };
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+        let Some((t_enter, t_exit)) = self.bounds.hit_range(r, &Interval::universe()) else {
+            return false;
+        };
+
+        let t = if interval.sourrounds(t_enter) {
+            t_enter
+        } else if interval.sourrounds(t_exit) {
+            t_exit
+        } else {
+            return false;
+        };
+
+        hit.t = t;
+        hit.p = r.at(t);
+        let (outward_normal, uv, dpdu, dpdv) = self.surface(&hit.p);
+        hit.set_face_normal(r, &outward_normal);
+        (hit.u, hit.v) = uv;
+        (hit.dpdu, hit.dpdv) = (dpdu, dpdv);
+        hit.material = Some(Arc::clone(&self.material));
+
+        return true;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "cuboid",
+            &[
+                &self.bounds.min.to_bits()[..],
+                &self.bounds.max.to_bits(),
+                &[self.material.content_hash()],
+            ]
+            .concat(),
+        );
+    }
+}
+
+impl Solid for Cuboid {
+    fn spans(&self, r: &Ray) -> Vec<Span> {
+        return match self.bounds.hit_range(r, &Interval::universe()) {
+            Some((t_enter, t_exit)) => vec![Span {
+                enter: self.boundary(r, t_enter),
+                exit: self.boundary(r, t_exit),
+            }],
+            None => vec![],
+        };
+    }
+}
@@ -0,0 +1,125 @@
+use std::{fs, io, path::Path};
+
+use crate::{tile::Tile, utils, vec3::Color};
+
+/// Running mean and variance (Welford's algorithm).
+#[derive(Clone, Copy, Default)]
+pub struct Welford {
+    pub count: u64,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl Welford {
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Standard error of the mean relative to the mean. The floor keeps
+    /// (almost) black pixels from needing endless samples.
+    pub fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.count as f64;
+        let variance = self.m2 / (n - 1.);
+        return (variance / n).sqrt() / self.mean.abs().max(1e-3);
+    }
+}
+
+/// Accumulated radiance of one pixel plus running statistics of its
+/// luminance for convergence tests. Samples carry the sign of the
+/// reconstruction filter as their weight.
+#[derive(Clone, Copy, Default)]
+pub struct FilmPixel {
+    pub sum: Color,
+    pub weight_sum: f64,
+    pub luminance: Welford,
+}
+
+impl FilmPixel {
+    pub fn add(&mut self, sample: &Color, weight: f64) {
+        self.sum += sample.mul(weight);
+        self.weight_sum += weight;
+        self.luminance.push(weight * utils::luminance(sample));
+    }
+
+    /// Samples taken so far
+    pub fn count(&self) -> u64 {
+        return self.luminance.count;
+    }
+
+    pub fn color(&self) -> Color {
+        if self.weight_sum.abs() < 1e-12 {
+            return Color::default();
+        }
+        return self.sum.div(self.weight_sum);
+    }
+
+    /// See `Welford::relative_error`.
+    pub fn relative_error(&self) -> f64 {
+        return self.luminance.relative_error();
+    }
+}
+
+/// Row-major linear image the camera accumulates samples into.
+pub struct Film {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Self {
+        return Self {
+            width,
+            height,
+            pixels: vec![FilmPixel::default(); width * height],
+        };
+    }
+
+    /// Copy of the pixels under `tile`, row by row.
+    pub fn read_tile(&self, tile: &Tile) -> Vec<FilmPixel> {
+        let mut out = Vec::with_capacity(tile.width() * tile.height());
+        for y in tile.y0..tile.y1 {
+            let row = y * self.width;
+            out.extend_from_slice(&self.pixels[row + tile.x0..row + tile.x1]);
+        }
+        return out;
+    }
+
+    /// Puts a buffer from `read_tile` back.
+    pub fn write_tile(&mut self, tile: &Tile, pixels: &[FilmPixel]) {
+        for (y, src) in (tile.y0..tile.y1).zip(pixels.chunks_exact(tile.width())) {
+            let row = y * self.width;
+            self.pixels[row + tile.x0..row + tile.x1].copy_from_slice(src);
+        }
+    }
+
+    pub fn total_samples(&self) -> u64 {
+        return self.pixels.iter().map(|p| p.count()).sum();
+    }
+
+    /// Average relative error over all pixels, see `FilmPixel::relative_error`.
+    pub fn mean_error(&self) -> f64 {
+        let sum: f64 = self.pixels.iter().map(|p| p.relative_error()).sum();
+        return sum / self.pixels.len().max(1) as f64;
+    }
+
+    /// Writes a gamma 2 P3 PPM. Goes through a temporary file, so viewers
+    /// watching `path` never see half an image.
+    pub fn write_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut out = format!("P3\n{} {}\n255\n", self.width, self.height);
+        for pixel in &self.pixels {
+            utils::write_color(&mut out, &pixel.color());
+        }
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, out.as_bytes())?;
+        return fs::rename(tmp, path);
+    }
+}
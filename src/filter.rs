@@ -0,0 +1,150 @@
+#![allow(dead_code)]
+
+use core::f64;
+
+/// Pixel reconstruction filters, all separable. The camera importance samples
+/// them: pixel sample positions follow `|f|`, and the film normalizes by the
+/// sum of the signs, which handles the negative lobes of Mitchell and Lanczos.
+/// Radii are in pixels, anything above 0.5 reaches into the neighbours.
+#[derive(Clone, Copy, Debug)]
+pub enum Filter {
+    Box { radius: f64 },
+    Tent { radius: f64 },
+    Gaussian { radius: f64, sigma: f64 },
+    Mitchell { radius: f64, b: f64, c: f64 },
+    Lanczos { radius: f64, tau: f64 },
+}
+
+impl Filter {
+    /// Mitchell–Netravali with the recommended `B = C = 1/3`.
+    pub fn mitchell() -> Self {
+        return Self::Mitchell {
+            radius: 2.,
+            b: 1. / 3.,
+            c: 1. / 3.,
+        };
+    }
+
+    pub fn lanczos() -> Self {
+        return Self::Lanczos {
+            radius: 2.,
+            tau: 2.,
+        };
+    }
+
+    pub fn radius(&self) -> f64 {
+        return match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius, .. } => radius,
+        };
+    }
+
+    /// The kind followed by the raw bits of every parameter, for hashing.
+    pub fn to_bits(self) -> Vec<u64> {
+        return match self {
+            Self::Box { radius } => vec![0, radius.to_bits()],
+            Self::Tent { radius } => vec![1, radius.to_bits()],
+            Self::Gaussian { radius, sigma } => vec![2, radius.to_bits(), sigma.to_bits()],
+            Self::Mitchell { radius, b, c } => {
+                vec![3, radius.to_bits(), b.to_bits(), c.to_bits()]
+            }
+            Self::Lanczos { radius, tau } => vec![4, radius.to_bits(), tau.to_bits()],
+        };
+    }
+
+    /// One axis of the filter, `x` in pixels from the pixel center.
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.;
+        }
+
+        return match *self {
+            Self::Box { .. } => 1.,
+            Self::Tent { radius } => radius - x,
+            Self::Gaussian { radius, sigma } => {
+                let gaussian = |x: f64| (-x * x / (2. * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.)
+            }
+            Self::Mitchell { radius, b, c } => {
+                let x = 2. * x / radius;
+                let value = if x <= 1. {
+                    (12. - 9. * b - 6. * c) * x * x * x
+                        + (-18. + 12. * b + 6. * c) * x * x
+                        + (6. - 2. * b)
+                } else {
+                    (-b - 6. * c) * x * x * x
+                        + (6. * b + 30. * c) * x * x
+                        + (-12. * b - 48. * c) * x
+                        + (8. * b + 24. * c)
+                };
+                value / 6.
+            }
+            Self::Lanczos { tau, .. } => sinc(x) * sinc(x / tau),
+        };
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        return Self::Box { radius: 0.5 };
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.;
+    }
+    let x = f64::consts::PI * x;
+    return x.sin() / x;
+}
+
+const TABLE_SIZE: usize = 256;
+
+/// Piecewise constant tabulation of one filter axis, sampled by inverting its CDF.
+pub struct FilterSampler {
+    radius: f64,
+    values: Vec<f64>,
+    cdf: Vec<f64>,
+}
+
+impl FilterSampler {
+    pub fn new(filter: &Filter) -> Self {
+        let radius = filter.radius().max(1e-3);
+        let width = 2. * radius / TABLE_SIZE as f64;
+        let values: Vec<f64> = (0..TABLE_SIZE)
+            .map(|i| filter.evaluate(-radius + (i as f64 + 0.5) * width))
+            .collect();
+
+        let mut cdf = Vec::with_capacity(TABLE_SIZE + 1);
+        cdf.push(0.);
+        for v in &values {
+            cdf.push(cdf.last().unwrap() + v.abs());
+        }
+        let total = *cdf.last().unwrap();
+        for c in cdf.iter_mut() {
+            *c = if total > 0. { *c / total } else { 0. };
+        }
+
+        return Self {
+            radius,
+            values,
+            cdf,
+        };
+    }
+
+    /// Offset from the pixel center and the sign of the filter there. The
+    /// magnitude of `f / pdf` is the same everywhere, so the sign is the weight.
+    pub fn sample(&self, u: f64) -> (f64, f64) {
+        let bin = self.cdf.partition_point(|c| *c <= u).clamp(1, TABLE_SIZE) - 1;
+        let (lo, hi) = (self.cdf[bin], self.cdf[bin + 1]);
+        let t = if hi > lo { (u - lo) / (hi - lo) } else { 0.5 };
+
+        let width = 2. * self.radius / TABLE_SIZE as f64;
+        let x = -self.radius + (bin as f64 + t) * width;
+        return (x, self.values[bin].signum());
+    }
+}
@@ -0,0 +1,583 @@
+#![allow(dead_code)]
+
+use std::{fs, io, path::Path, sync::Arc};
+
+use serde_json::Value;
+
+use crate::{
+    camera::Camera,
+    hit::Hittable,
+    material::{principled::Principled, Material},
+    mesh::TriangleMesh,
+    texture::{Scaled, VertexColor},
+    vec3::{Color, Point3, Vec3},
+};
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A;
+const GLB_CHUNK_BIN: u32 = 0x004E4942;
+
+/// Everything we take from a glTF file: one mesh per triangle primitive,
+/// already transformed into world space, and every perspective camera.
+pub struct GltfScene {
+    pub objects: Vec<Arc<dyn Hittable>>,
+    pub cameras: Vec<GltfCamera>,
+}
+
+pub struct GltfCamera {
+    pub position: Point3,
+    pub forward: Vec3,
+    pub up: Vec3,
+    /// Degrees, like `Camera::vertical_fov`
+    pub vertical_fov: f64,
+    pub aspect_ratio: Option<f64>,
+}
+
+impl GltfCamera {
+    pub fn apply(&self, cam: &mut Camera) {
+        cam.look_from = self.position;
+        cam.look_at = self.position + self.forward;
+        cam.vup = self.up;
+        cam.vertical_fov = self.vertical_fov;
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            cam.aspect_ratio = aspect_ratio;
+        }
+    }
+}
+
+/// Column-major 4x4 matrix, as glTF stores them.
+#[derive(Clone, Copy)]
+struct Mat4([f64; 16]);
+
+impl Mat4 {
+    fn identity() -> Self {
+        let mut m = [0.; 16];
+        m[0] = 1.;
+        m[5] = 1.;
+        m[10] = 1.;
+        m[15] = 1.;
+        return Self(m);
+    }
+
+    fn at(&self, row: usize, col: usize) -> f64 {
+        return self.0[col * 4 + row];
+    }
+
+    fn mul(&self, rhs: &Mat4) -> Mat4 {
+        let mut m = [0.; 16];
+        for col in 0..4 {
+            for row in 0..4 {
+                m[col * 4 + row] = (0..4).map(|k| self.at(row, k) * rhs.at(k, col)).sum();
+            }
+        }
+        return Mat4(m);
+    }
+
+    fn from_trs(t: [f64; 3], r: [f64; 4], s: [f64; 3]) -> Self {
+        let [x, y, z, w] = r;
+        let rot = [
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - z * w),
+                2. * (x * z + y * w),
+            ],
+            [
+                2. * (x * y + z * w),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - x * w),
+            ],
+            [
+                2. * (x * z - y * w),
+                2. * (y * z + x * w),
+                1. - 2. * (x * x + y * y),
+            ],
+        ];
+
+        let mut m = Self::identity().0;
+        for col in 0..3 {
+            for row in 0..3 {
+                m[col * 4 + row] = rot[row][col] * s[col];
+            }
+        }
+        m[12] = t[0];
+        m[13] = t[1];
+        m[14] = t[2];
+        return Self(m);
+    }
+
+    fn point(&self, p: &Point3) -> Point3 {
+        return Point3::from_floats(
+            self.at(0, 0) * p.0 + self.at(0, 1) * p.1 + self.at(0, 2) * p.2 + self.at(0, 3),
+            self.at(1, 0) * p.0 + self.at(1, 1) * p.1 + self.at(1, 2) * p.2 + self.at(1, 3),
+            self.at(2, 0) * p.0 + self.at(2, 1) * p.1 + self.at(2, 2) * p.2 + self.at(2, 3),
+        );
+    }
+
+    fn vector(&self, v: &Vec3) -> Vec3 {
+        return Vec3(
+            self.at(0, 0) * v.0 + self.at(0, 1) * v.1 + self.at(0, 2) * v.2,
+            self.at(1, 0) * v.0 + self.at(1, 1) * v.1 + self.at(1, 2) * v.2,
+            self.at(2, 0) * v.0 + self.at(2, 1) * v.1 + self.at(2, 2) * v.2,
+        );
+    }
+
+    fn det3(&self) -> f64 {
+        let a = |r, c| self.at(r, c);
+        return a(0, 0) * (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1))
+            - a(0, 1) * (a(1, 0) * a(2, 2) - a(1, 2) * a(2, 0))
+            + a(0, 2) * (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0));
+    }
+
+    /// Normals transform with the inverse transpose. The cofactor matrix is that
+    /// scaled by the determinant, which doesn't matter as they get normalized.
+    fn normal(&self, n: &Vec3) -> Vec3 {
+        let c0 = Vec3(self.at(0, 0), self.at(1, 0), self.at(2, 0));
+        let c1 = Vec3(self.at(0, 1), self.at(1, 1), self.at(2, 1));
+        let c2 = Vec3(self.at(0, 2), self.at(1, 2), self.at(2, 2));
+        let (r0, r1, r2) = (c1.cross(&c2), c2.cross(&c0), c0.cross(&c1));
+
+        let n = Vec3(
+            r0.0 * n.0 + r1.0 * n.1 + r2.0 * n.2,
+            r0.1 * n.0 + r1.1 * n.1 + r2.1 * n.2,
+            r0.2 * n.0 + r1.2 * n.1 + r2.2 * n.2,
+        );
+        return n.mul(self.det3().signum()).norm();
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, msg.into());
+}
+
+fn decode_base64(data: &str) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+
+    for c in data
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(invalid("Invalid base64 data")),
+        };
+
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+
+    return Ok(out);
+}
+
+struct Loader {
+    json: Value,
+    buffers: Vec<Vec<u8>>,
+    /// One material per glTF material, the last one is the default material
+    materials: Vec<Arc<dyn Material>>,
+}
+
+/// Loads a local `.gltf` (with external or embedded buffers) or `.glb` file.
+/// Only triangle primitives and perspective cameras are imported. Materials
+/// use their factors and vertex colors. Files whose materials need base color,
+/// metallic-roughness or normal textures are refused, there is no PNG or JPEG
+/// decoder to load them with and dropping them would silently change the look.
+pub fn load(path: impl AsRef<Path>) -> io::Result<GltfScene> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let read_u32 = |b: &[u8], i: usize| u32::from_le_bytes(b[i..i + 4].try_into().unwrap());
+
+    let (json, mut glb_bin) = if bytes.len() >= 12 && read_u32(&bytes, 0) == GLB_MAGIC {
+        let mut json = None;
+        let mut bin = None;
+        let mut pos = 12;
+        while pos + 8 <= bytes.len() {
+            let len = read_u32(&bytes, pos) as usize;
+            let kind = read_u32(&bytes, pos + 4);
+            let chunk = bytes
+                .get(pos + 8..pos + 8 + len)
+                .ok_or_else(|| invalid("Truncated GLB chunk"))?;
+            match kind {
+                GLB_CHUNK_JSON => json = Some(chunk),
+                GLB_CHUNK_BIN => bin = Some(chunk.to_vec()),
+                _ => {}
+            }
+            pos += 8 + len;
+        }
+
+        let json = json.ok_or_else(|| invalid("GLB file has no JSON chunk"))?;
+        (serde_json::from_slice::<Value>(json), bin)
+    } else {
+        (serde_json::from_slice::<Value>(&bytes), None)
+    };
+    let json = json.map_err(|e| invalid(format!("Invalid glTF JSON: {e}")))?;
+
+    let mut buffers = Vec::new();
+    for buffer in json["buffers"].as_array().into_iter().flatten() {
+        let data = match buffer["uri"].as_str() {
+            Some(uri) if uri.starts_with("data:") => {
+                let (_, data) = uri
+                    .split_once(";base64,")
+                    .ok_or_else(|| invalid("Only base64 data URIs are supported"))?;
+                decode_base64(data)?
+            }
+            Some(uri) => fs::read(base_dir.join(uri))?,
+            // The GLB binary chunk
+            None => glb_bin
+                .take()
+                .ok_or_else(|| invalid("Buffer without uri outside of a GLB file"))?,
+        };
+        buffers.push(data);
+    }
+
+    let mut loader = Loader {
+        json,
+        buffers,
+        materials: vec![],
+    };
+    loader.load_materials()?;
+
+    let mut scene = GltfScene {
+        objects: vec![],
+        cameras: vec![],
+    };
+
+    let scene_idx = loader.json["scene"].as_u64().unwrap_or(0) as usize;
+    let roots: Vec<usize> = match loader.json["scenes"].get(scene_idx) {
+        Some(s) => indices(&s["nodes"]),
+        // No scenes, every node that isn't somebody's child is a root
+        None => {
+            let nodes = loader.json["nodes"].as_array().map_or(0, |n| n.len());
+            let children: Vec<usize> = loader.json["nodes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .flat_map(|n| indices(&n["children"]))
+                .collect();
+            (0..nodes).filter(|n| !children.contains(n)).collect()
+        }
+    };
+
+    for root in roots {
+        loader.load_node(root, &Mat4::identity(), &mut scene, 0)?;
+    }
+
+    return Ok(scene);
+}
+
+fn indices(v: &Value) -> Vec<usize> {
+    return v
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|i| i.as_u64().map(|i| i as usize))
+        .collect();
+}
+
+fn floats<const N: usize>(v: &Value, default: [f64; N]) -> [f64; N] {
+    let mut out = default;
+    if let Some(values) = v.as_array() {
+        for (o, v) in out.iter_mut().zip(values) {
+            *o = v.as_f64().unwrap_or(*o);
+        }
+    }
+    return out;
+}
+
+impl Loader {
+    fn load_materials(&mut self) -> io::Result<()> {
+        let materials = self.json["materials"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        // Appending the default material means `material: None` maps to the last entry
+        for (idx, mat) in materials.iter().chain([&Value::Null]).enumerate() {
+            let pbr = &mat["pbrMetallicRoughness"];
+            let textures = [
+                ("baseColorTexture", &pbr["baseColorTexture"]),
+                ("metallicRoughnessTexture", &pbr["metallicRoughnessTexture"]),
+                ("normalTexture", &mat["normalTexture"]),
+            ];
+            if let Some((name, _)) = textures.iter().find(|(_, t)| !t.is_null()) {
+                return Err(invalid(format!(
+                    "glTF material {idx} uses a {name}, textures are not supported"
+                )));
+            }
+
+            let [r, g, b, _] = floats(&pbr["baseColorFactor"], [1., 1., 1., 1.]);
+
+            // Vertex colors, when a primitive has them, multiply the base color
+            let base_color = Scaled {
+                texture: Arc::new(VertexColor {
+                    fallback: Color::from_floats(1., 1., 1.),
+                }),
+                factor: Color::from_floats(r, g, b),
+            };
+
+            let ext = &mat["extensions"];
+            self.materials.push(Arc::new(Principled {
+                base_color: Arc::new(base_color),
+                metallic: pbr["metallicFactor"].as_f64().unwrap_or(1.),
+                roughness: pbr["roughnessFactor"].as_f64().unwrap_or(1.),
+                transmission: ext["KHR_materials_transmission"]["transmissionFactor"]
+                    .as_f64()
+                    .unwrap_or(0.),
+                clearcoat: ext["KHR_materials_clearcoat"]["clearcoatFactor"]
+                    .as_f64()
+                    .unwrap_or(0.),
+                clearcoat_roughness: ext["KHR_materials_clearcoat"]["clearcoatRoughnessFactor"]
+                    .as_f64()
+                    .unwrap_or(0.),
+                ior: ext["KHR_materials_ior"]["ior"].as_f64().unwrap_or(1.5),
+                ..Default::default()
+            }));
+        }
+
+        return Ok(());
+    }
+
+    fn load_node(
+        &self,
+        idx: usize,
+        parent: &Mat4,
+        scene: &mut GltfScene,
+        depth: usize,
+    ) -> io::Result<()> {
+        // Guards against malformed files with cyclic node graphs
+        if depth > 256 {
+            return Err(invalid("glTF node hierarchy is too deep"));
+        }
+
+        let node = self.json["nodes"]
+            .get(idx)
+            .ok_or_else(|| invalid(format!("Missing glTF node {idx}")))?;
+
+        let local = match node["matrix"].as_array() {
+            Some(_) => Mat4(floats(&node["matrix"], Mat4::identity().0)),
+            None => Mat4::from_trs(
+                floats(&node["translation"], [0., 0., 0.]),
+                floats(&node["rotation"], [0., 0., 0., 1.]),
+                floats(&node["scale"], [1., 1., 1.]),
+            ),
+        };
+        let world = parent.mul(&local);
+
+        if let Some(mesh) = node["mesh"].as_u64() {
+            self.load_mesh(mesh as usize, &world, scene)?;
+        }
+
+        if let Some(cam) = node["camera"].as_u64() {
+            let perspective = &self.json["cameras"][cam as usize]["perspective"];
+            if let Some(yfov) = perspective["yfov"].as_f64() {
+                scene.cameras.push(GltfCamera {
+                    position: world.point(&Point3::default()),
+                    forward: world.vector(&Vec3(0., 0., -1.)).norm(),
+                    up: world.vector(&Vec3(0., 1., 0.)).norm(),
+                    vertical_fov: yfov.to_degrees(),
+                    aspect_ratio: perspective["aspectRatio"].as_f64(),
+                });
+            }
+        }
+
+        for child in indices(&node["children"]) {
+            self.load_node(child, &world, scene, depth + 1)?;
+        }
+
+        return Ok(());
+    }
+
+    fn load_mesh(&self, idx: usize, world: &Mat4, scene: &mut GltfScene) -> io::Result<()> {
+        let primitives = self.json["meshes"][idx]["primitives"]
+            .as_array()
+            .ok_or_else(|| invalid(format!("glTF mesh {idx} has no primitives")))?;
+
+        // Mirroring transforms flip the winding order
+        let mirrored = world.det3() < 0.;
+
+        for prim in primitives {
+            // Triangles only
+            if prim["mode"].as_u64().unwrap_or(4) != 4 {
+                continue;
+            }
+
+            let attributes = &prim["attributes"];
+            let Some(position) = attributes["POSITION"].as_u64() else {
+                continue;
+            };
+
+            let positions: Vec<Point3> = self
+                .read_accessor(position as usize, &["VEC3"])?
+                .iter()
+                .map(|p| world.point(&Point3::from_floats(p[0], p[1], p[2])))
+                .collect();
+
+            let normals = match attributes["NORMAL"].as_u64() {
+                Some(acc) => self
+                    .read_accessor(acc as usize, &["VEC3"])?
+                    .iter()
+                    .map(|n| world.normal(&Vec3(n[0], n[1], n[2])))
+                    .collect(),
+                None => vec![],
+            };
+
+            let uvs = match attributes["TEXCOORD_0"].as_u64() {
+                Some(acc) => self
+                    .read_accessor(acc as usize, &["VEC2"])?
+                    .iter()
+                    .map(|uv| (uv[0], 1. - uv[1]))
+                    .collect(),
+                None => vec![],
+            };
+
+            let colors = match attributes["COLOR_0"].as_u64() {
+                Some(acc) => self
+                    .read_accessor(acc as usize, &["VEC3", "VEC4"])?
+                    .iter()
+                    .map(|c| Color::from_floats(c[0], c[1], c[2]))
+                    .collect(),
+                None => vec![],
+            };
+
+            let flat: Vec<usize> = match prim["indices"].as_u64() {
+                Some(acc) => self
+                    .read_accessor(acc as usize, &["SCALAR"])?
+                    .iter()
+                    .map(|i| i[0] as usize)
+                    .collect(),
+                None => (0..positions.len()).collect(),
+            };
+            let triangles: Vec<[usize; 3]> = flat
+                .chunks_exact(3)
+                .map(|t| {
+                    if mirrored {
+                        [t[0], t[2], t[1]]
+                    } else {
+                        [t[0], t[1], t[2]]
+                    }
+                })
+                .collect();
+
+            if triangles.iter().flatten().any(|i| *i >= positions.len()) {
+                return Err(invalid("glTF primitive references a missing vertex"));
+            }
+
+            let material = match prim["material"].as_u64() {
+                Some(m) => self.materials.get(m as usize),
+                None => self.materials.last(),
+            }
+            .ok_or_else(|| invalid("glTF primitive references a missing material"))?;
+
+            scene.objects.push(Arc::new(TriangleMesh::new(
+                positions,
+                normals,
+                colors,
+                uvs,
+                triangles,
+                Arc::clone(material),
+            )));
+        }
+
+        return Ok(());
+    }
+
+    /// Reads every element of an accessor as floats, normalized integers
+    /// are mapped to `[0, 1]` (or `[-1, 1]` when signed). Fails unless the
+    /// accessor's type is one of `types`, callers index into the elements.
+    fn read_accessor(&self, idx: usize, types: &[&str]) -> io::Result<Vec<Vec<f64>>> {
+        let acc = &self.json["accessors"][idx];
+        let count = acc["count"].as_u64().unwrap_or(0) as usize;
+        let kind = acc["type"].as_str().unwrap_or("");
+        if !types.contains(&kind) {
+            return Err(invalid(format!(
+                "glTF accessor {idx} is {kind:?}, expected one of {types:?}"
+            )));
+        }
+        let components = match kind {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            _ => return Err(invalid(format!("Unsupported glTF accessor type in {idx}"))),
+        };
+        let component_type = acc["componentType"].as_u64().unwrap_or(0);
+        let normalized = acc["normalized"].as_bool().unwrap_or(false);
+
+        let (size, max): (usize, f64) = match component_type {
+            5120 => (1, i8::MAX as f64),
+            5121 => (1, u8::MAX as f64),
+            5122 => (2, i16::MAX as f64),
+            5123 => (2, u16::MAX as f64),
+            5125 => (4, u32::MAX as f64),
+            5126 => (4, 1.),
+            _ => return Err(invalid(format!("Unsupported glTF component type in {idx}"))),
+        };
+
+        // Sparse accessors may leave out the buffer view, we can't read those
+        let Some(view_idx) = acc["bufferView"].as_u64() else {
+            return Err(invalid(format!(
+                "glTF accessor {idx} has no buffer view, sparse accessors are not supported"
+            )));
+        };
+        let view = &self.json["bufferViews"][view_idx as usize];
+        let buffer = self
+            .buffers
+            .get(view["buffer"].as_u64().unwrap_or(0) as usize)
+            .ok_or_else(|| invalid("glTF buffer view references a missing buffer"))?;
+
+        let offset = (view["byteOffset"].as_u64().unwrap_or(0) as usize)
+            .checked_add(acc["byteOffset"].as_u64().unwrap_or(0) as usize);
+        let stride = view["byteStride"]
+            .as_u64()
+            .map_or(size * components, |s| s as usize);
+
+        // The counts come from the file, check the whole range before touching it
+        let end = match count {
+            0 => offset,
+            _ => (count - 1)
+                .checked_mul(stride)
+                .and_then(|n| n.checked_add(components * size))
+                .and_then(|n| n.checked_add(offset?)),
+        };
+        let offset = match (offset, end) {
+            (Some(offset), Some(end)) if end <= buffer.len() => offset,
+            _ => return Err(invalid("glTF accessor reads past its buffer")),
+        };
+
+        let mut out = Vec::with_capacity(count);
+        for i in 0..count {
+            let mut element = Vec::with_capacity(components);
+            for c in 0..components {
+                let at = offset + i * stride + c * size;
+                let b = &buffer[at..at + size];
+
+                let value = match component_type {
+                    5120 => b[0] as i8 as f64,
+                    5121 => b[0] as f64,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    5125 => u32::from_le_bytes(b.try_into().unwrap()) as f64,
+                    _ => f32::from_le_bytes(b.try_into().unwrap()) as f64,
+                };
+
+                element.push(if normalized && component_type != 5126 {
+                    (value / max).max(-1.)
+                } else {
+                    value
+                });
+            }
+            out.push(element);
+        }
+
+        return Ok(out);
+    }
+}
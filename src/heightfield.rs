@@ -0,0 +1,272 @@
+#![allow(dead_code)]
+
+use std::{io, path::Path, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, Hittable},
+    image::Image,
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    sampler, triangle,
+    vec3::{Point3, Vec3},
+};
+
+/// Terrain made from a regular grid of heights over the xz-plane. Every grid
+/// cell is split into two triangles, shading uses interpolated vertex normals.
+pub struct Heightfield {
+    pub nx: usize,
+    pub nz: usize,
+    pub heights: Vec<f64>,
+    pub material: Arc<dyn Material>,
+    normals: Vec<Vec3>,
+    bounds: Aabb,
+    cell_x: f64,
+    cell_z: f64,
+}
+
+impl Heightfield {
+    /// `heights` are absolute y values, row-major with x varying fastest. The
+    /// grid spans `size` (x, z) starting at `corner` (x, z).
+    pub fn new(
+        nx: usize,
+        nz: usize,
+        heights: Vec<f64>,
+        corner: (f64, f64),
+        size: (f64, f64),
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let (min_x, min_z) = corner;
+        let (size_x, size_z) = size;
+        assert!(
+            nx >= 2 && nz >= 2,
+            "A heightfield needs at least 2x2 samples"
+        );
+        assert_eq!(nx * nz, heights.len(), "Heights don't match the grid size");
+
+        let cell_x = size_x / (nx - 1) as f64;
+        let cell_z = size_z / (nz - 1) as f64;
+
+        let (min_y, max_y) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), h| {
+                (lo.min(*h), hi.max(*h))
+            });
+        // Padded so a completely flat field still has a volume to clip against
+        let bounds = Aabb::new(
+            Point3::from_floats(min_x, min_y - 1e-6, min_z),
+            Point3::from_floats(min_x + size_x, max_y + 1e-6, min_z + size_z),
+        );
+
+        let mut field = Self {
+            nx,
+            nz,
+            heights,
+            material,
+            normals: vec![],
+            bounds,
+            cell_x,
+            cell_z,
+        };
+        field.normals = (0..nz)
+            .flat_map(|z| (0..nx).map(move |x| (x, z)))
+            .map(|(x, z)| field.vertex_normal(x, z))
+            .collect();
+
+        return field;
+    }
+
+    /// Samples `height(x, z)` at every grid point, given in world coordinates.
+    pub fn from_fn(
+        nx: usize,
+        nz: usize,
+        corner: (f64, f64),
+        size: (f64, f64),
+        material: Arc<dyn Material>,
+        height: impl Fn(f64, f64) -> f64,
+    ) -> Self {
+        let heights = (0..nz)
+            .flat_map(|z| (0..nx).map(move |x| (x, z)))
+            .map(|(x, z)| {
+                height(
+                    corner.0 + size.0 * x as f64 / (nx - 1) as f64,
+                    corner.1 + size.1 * z as f64 / (nz - 1) as f64,
+                )
+            })
+            .collect();
+
+        return Self::new(nx, nz, heights, corner, size, material);
+    }
+
+    /// Uses the brightness of every pixel of a PPM image as height, scaled to
+    /// `[min.y, min.y + size.y]`. The image's top row ends up at `min.z`.
+    /// Values are taken as stored, heightmaps aren't gamma encoded.
+    pub fn from_image(
+        path: impl AsRef<Path>,
+        min: Point3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> io::Result<Self> {
+        let img = Image::load_ppm_linear(path)?;
+        if img.width < 2 || img.height < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "A heightfield image needs at least 2x2 pixels",
+            ));
+        }
+
+        let heights = img
+            .pixels
+            .iter()
+            .map(|c| min.1 + size.1 * (c.0 + c.1 + c.2) / 3.)
+            .collect();
+
+        return Ok(Self::new(
+            img.width,
+            img.height,
+            heights,
+            (min.0, min.2),
+            (size.0, size.2),
+            material,
+        ));
+    }
+
+    fn height(&self, x: usize, z: usize) -> f64 {
+        return self.heights[z * self.nx + x];
+    }
+
+    fn vertex(&self, x: usize, z: usize) -> Point3 {
+        return Point3::from_floats(
+            self.bounds.min.0 + x as f64 * self.cell_x,
+            self.height(x, z),
+            self.bounds.min.2 + z as f64 * self.cell_z,
+        );
+    }
+
+    fn vertex_normal(&self, x: usize, z: usize) -> Vec3 {
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(self.nx - 1));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(self.nz - 1));
+
+        let dx = (self.height(x1, z) - self.height(x0, z)) / ((x1 - x0) as f64 * self.cell_x);
+        let dz = (self.height(x, z1) - self.height(x, z0)) / ((z1 - z0) as f64 * self.cell_z);
+
+        return Vec3(-dx, 1., -dz).norm();
+    }
+
+    /// Closest hit with one of the two triangles of cell `(x, z)`: the
+    /// distance, the flat triangle's upward normal and the smooth one.
+    fn hit_cell(
+        &self,
+        r: &Ray,
+        interval: &Interval,
+        x: usize,
+        z: usize,
+    ) -> Option<(f64, Vec3, Vec3)> {
+        let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+        let mut closest = None;
+        let mut interval = Interval::new(interval.min, interval.max);
+
+        for tri in [[0, 1, 2], [0, 2, 3]] {
+            let [a, b, c] = tri.map(|i| corners[i]);
+            let [pa, pb, pc] = [a, b, c].map(|(x, z)| self.vertex(x, z));
+
+            if let Some((t, b1, b2)) = triangle::intersect(r, &interval, &pa, &pb, &pc) {
+                let n = |(x, z): (usize, usize)| self.normals[z * self.nx + x];
+                let normal = (n(a).mul(1. - b1 - b2) + n(b).mul(b1) + n(c).mul(b2)).norm();
+                let mut flat = (pb - pa).cross(&(pc - pa)).norm();
+                if flat.1 < 0. {
+                    flat = flat.inv();
+                }
+                closest = Some((t, flat, normal));
+                interval.max = t;
+            }
+        }
+
+        return closest;
+    }
+}
+
+impl Hittable for Heightfield {
+    // Walks the grid cells under the ray front to back (2D DDA), so the first
+    // cell with a hit holds the closest one.
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+        let Some((t_enter, t_exit)) = self.bounds.hit_range(r, interval) else {
+            return false;
+        };
+
+        let start = r.at(t_enter);
+        let cell = |p: f64, min: f64, size: f64, n: usize| {
+            (((p - min) / size).floor().max(0.) as usize).min(n - 2)
+        };
+        let mut x = cell(start.0, self.bounds.min.0, self.cell_x, self.nx);
+        let mut z = cell(start.2, self.bounds.min.2, self.cell_z, self.nz);
+
+        let axis_setup = |dir: f64, origin: f64, min: f64, size: f64, idx: usize| {
+            if dir > 0. {
+                (
+                    1,
+                    (min + (idx + 1) as f64 * size - origin) / dir,
+                    size / dir,
+                )
+            } else if dir < 0. {
+                (-1, (min + idx as f64 * size - origin) / dir, -size / dir)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let (step_x, mut next_x, delta_x) =
+            axis_setup(r.direction.0, r.origin.0, self.bounds.min.0, self.cell_x, x);
+        let (step_z, mut next_z, delta_z) =
+            axis_setup(r.direction.2, r.origin.2, self.bounds.min.2, self.cell_z, z);
+
+        loop {
+            if let Some((t, flat, normal)) = self.hit_cell(r, interval, x, z) {
+                hit.t = t;
+                hit.p = r.at(t);
+                hit.set_face_normal(r, &flat);
+                hit.set_shading_normal(&normal);
+                hit.material = Some(Arc::clone(&self.material));
+                hit.u = (hit.p.0 - self.bounds.min.0) / self.bounds.extent().0;
+                hit.v = (hit.p.2 - self.bounds.min.2) / self.bounds.extent().2;
+                // Tangents of the surface y = h(x, z) follow from its normal
+                hit.dpdu = Vec3(1., -normal.0 / normal.1, 0.).mul(self.bounds.extent().0);
+                hit.dpdv = Vec3(0., -normal.2 / normal.1, 1.).mul(self.bounds.extent().2);
+                return true;
+            }
+
+            if next_x < next_z {
+                if next_x > t_exit {
+                    return false;
+                }
+                let Some(next) = x.checked_add_signed(step_x).filter(|i| *i < self.nx - 1) else {
+                    return false;
+                };
+                x = next;
+                next_x += delta_x;
+            } else {
+                if next_z > t_exit {
+                    return false;
+                }
+                let Some(next) = z.checked_add_signed(step_z).filter(|i| *i < self.nz - 1) else {
+                    return false;
+                };
+                z = next;
+                next_z += delta_z;
+            }
+        }
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "heightfield",
+            &[
+                &[self.nx as u64, self.nz as u64, self.material.content_hash()][..],
+                &self.bounds.min.to_bits(),
+                &self.bounds.max.to_bits(),
+                &self.heights.iter().map(|h| h.to_bits()).collect::<Vec<_>>(),
+            ]
+            .concat(),
+        );
+    }
+}
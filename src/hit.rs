@@ -1,17 +1,42 @@
 use std::sync::Arc;
 
 use crate::{
+    interval::Interval,
     material::{lambertian::Lambertian, Material},
     ray::Ray,
-    vec3::{Point3, Vec3},
+    sampler::BounceSample,
+    vec3::{Color, Point3, Vec3},
 };
 
+#[derive(Clone)]
 pub struct Hit {
     pub p: Point3,
+    /// Geometric normal, against the ray. Decides which side the ray is on.
     pub normal: Vec3,
+    /// Normal materials shade with, on the same side as `normal`. Differs from
+    /// it where a primitive interpolates vertex normals.
+    pub shading_normal: Vec3,
     pub t: f64,
     pub front_face: bool,
     pub material: Option<Arc<dyn Material>>,
+    /// Surface parameterization, used for texture lookups
+    pub u: f64,
+    pub v: f64,
+    pub vertex_color: Option<Color>,
+    /// Partial derivatives of `p` along `u` and `v`, zero where the primitive
+    /// has no parameterization. Normal mapping builds its tangent frame from them.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Sampler dimensions for scattering off this hit, set by the camera
+    pub sample: BounceSample,
+}
+
+pub trait Hittable: Send + Sync {
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool;
+
+    /// Hash of everything that decides how the object renders, materials
+    /// included. Checkpoints use it to refuse resuming a different scene.
+    fn content_hash(&self) -> u64;
 }
 
 impl Hit {
@@ -21,7 +46,34 @@ impl Hit {
             *outward_normal
         } else {
             outward_normal.inv()
+        };
+        self.shading_normal = self.normal;
+    }
+
+    /// Replaces the shading normal with a smooth one, facing the same way as
+    /// the outward normal given to `set_face_normal` before.
+    pub fn set_shading_normal(&mut self, outward_normal: &Vec3) {
+        self.shading_normal = if self.front_face {
+            *outward_normal
+        } else {
+            outward_normal.inv()
+        };
+    }
+
+    /// Unit tangents for surfaces without a parameterization: `dpdu` runs
+    /// around the y-axis like a sphere's, `dpdv` completes a right handed
+    /// frame with the outward normal. Both are zero at the poles, where
+    /// `Onb::from_normal_tangent` picks its own.
+    pub fn set_azimuthal_tangents(&mut self, outward_normal: &Vec3) {
+        let dpdu = Vec3(outward_normal.2, 0., -outward_normal.0);
+        if dpdu.is_near_zero() {
+            self.dpdu = Vec3::default();
+            self.dpdv = Vec3::default();
+            return;
         }
+
+        self.dpdu = dpdu.norm();
+        self.dpdv = outward_normal.cross(&self.dpdu);
     }
 }
 
@@ -30,9 +82,16 @@ impl Default for Hit {
         return Self {
             p: Point3::default(),
             normal: Vec3::default(),
+            shading_normal: Vec3::default(),
             t: 0.,
             front_face: true,
             material: Some(Arc::new(Lambertian::default())),
+            u: 0.,
+            v: 0.,
+            vertex_color: None,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            sample: BounceSample::default(),
         };
     }
 }
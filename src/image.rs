@@ -0,0 +1,135 @@
+#![allow(dead_code)]
+
+use std::{fs, io, path::Path};
+
+use crate::vec3::Color;
+
+/// Linear, row-major RGB image with the origin in the upper left corner.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    /// Loads an ASCII (P3) or binary (P6) PPM file. Values are scaled to `[0, 1]`
+    /// and converted from gamma 2 space, which is what `Film::write_ppm` writes.
+    pub fn load_ppm(path: impl AsRef<Path>) -> io::Result<Self> {
+        return Self::read_ppm(path.as_ref(), true);
+    }
+
+    /// Like `load_ppm` but keeps the values as stored, for data like normal maps.
+    pub fn load_ppm_linear(path: impl AsRef<Path>) -> io::Result<Self> {
+        return Self::read_ppm(path.as_ref(), false);
+    }
+
+    fn read_ppm(path: &Path, gamma: bool) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        // Header: magic, width, height, maxval separated by whitespace and comments
+        let mut pos = 0;
+        let mut header = Vec::with_capacity(4);
+        while header.len() < 4 {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos < bytes.len() && bytes[pos] == b'#' {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid("Truncated PPM header"));
+            }
+            header.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+        }
+        // Exactly one whitespace character separates the header from binary data
+        pos += 1;
+
+        let parse = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| invalid("Invalid number in PPM header"))
+        };
+        let width = parse(&header[1])?;
+        let height = parse(&header[2])?;
+        let max_val = parse(&header[3])?.max(1) as f64;
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid("PPM dimensions are too large"))?;
+
+        let values: Vec<f64> = match header[0].as_str() {
+            "P3" => bytes
+                .get(pos.min(bytes.len())..)
+                .unwrap_or_default()
+                .split(|b| b.is_ascii_whitespace())
+                .filter(|s| !s.is_empty())
+                .take(count)
+                .map(|s| String::from_utf8_lossy(s).parse::<f64>().unwrap_or(0.))
+                .collect(),
+            "P6" if max_val < 256. => bytes
+                .get(pos..)
+                .unwrap_or_default()
+                .iter()
+                .take(count)
+                .map(|b| *b as f64)
+                .collect(),
+            "P6" => bytes
+                .get(pos..)
+                .unwrap_or_default()
+                .chunks_exact(2)
+                .take(count)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as f64)
+                .collect(),
+            _ => return Err(invalid("Not a P3 or P6 PPM file")),
+        };
+
+        if values.len() != count {
+            return Err(invalid("PPM data doesn't match the image dimensions"));
+        }
+
+        let pixels = values
+            .chunks_exact(3)
+            .map(|c| {
+                let to_linear = |x: f64| {
+                    let x = x / max_val;
+                    if gamma {
+                        x * x
+                    } else {
+                        x
+                    }
+                };
+                Color::from_floats(to_linear(c[0]), to_linear(c[1]), to_linear(c[2]))
+            })
+            .collect();
+
+        return Ok(Self {
+            width,
+            height,
+            pixels,
+        });
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        let x = x.min(self.width - 1);
+        let y = y.min(self.height - 1);
+        return self.pixels[y * self.width + x];
+    }
+
+    /// Nearest pixel lookup with `v = 0` at the bottom of the image.
+    pub fn sample_uv(&self, u: f64, v: f64) -> Color {
+        let u = u.clamp(0., 1.);
+        let v = 1. - v.clamp(0., 1.);
+        return self.get(
+            (u * self.width as f64) as usize,
+            (v * self.height as f64) as usize,
+        );
+    }
+}
@@ -6,33 +6,54 @@ use core::f64;
 use std::sync::Arc;
 
 use camera::Camera;
+use checkpoint::Checkpoint;
 use material::{dialectric::Dialectric, lambertian::Lambertian, metal::Metal, Material};
 use ray::Ray;
 use sphere::Sphere;
 use vec3::{Color, Point3, Vec3};
 use world::World;
 
+mod aabb;
 mod camera;
+mod checkpoint;
+mod csg;
+mod cuboid;
+mod film;
+mod filter;
+mod gltf;
+mod heightfield;
 mod hit;
+mod image;
 mod interval;
 mod material;
+mod mesh;
+mod onb;
+mod progress;
 mod ray;
+mod sampler;
+mod sdf;
+mod spectrum;
 mod sphere;
+mod texture;
+mod tile;
+mod triangle;
 mod utils;
 mod vec3;
+mod volume;
 mod world;
 
 fn main() {
-    let mat_ground: Arc<dyn Material> = Arc::new(Lambertian {
-        albedo: Vec3(0.6, 0.5, 0.7),
-    });
+    // The random scene comes out the same on every run
+    utils::seed_rng(42);
+
+    let mat_ground: Arc<dyn Material> = Arc::new(Lambertian::new(Vec3(0.6, 0.5, 0.7)));
 
     let mut world = World::new();
-    world.push(Sphere::new(
+    world.push(Arc::new(Sphere::new(
         Point3::from_floats(0., -1000., 0.),
         1000.,
         Arc::clone(&mat_ground),
-    ));
+    )));
 
     for a in -11..11 {
         for b in -11..11 {
@@ -49,40 +70,56 @@ fn main() {
                 if material_mode < 0.7 {
                     // Diffuse
                     let albedo = Color::random() * Color::random();
-                    sphere_material = Arc::new(Lambertian { albedo });
-                    world.push(Sphere::new(center, 0.2, Arc::clone(&sphere_material)));
+                    sphere_material = Arc::new(Lambertian::new(albedo));
+                    world.push(Arc::new(Sphere::new(
+                        center,
+                        0.2,
+                        Arc::clone(&sphere_material),
+                    )));
                 } else if material_mode < 0.9 {
                     // Metal
                     let albedo = Color::random_range(0.5, 1.);
-                    let fuzz = utils::rand_float_range(0., 0.5);
-                    sphere_material = Arc::new(Metal { albedo, fuzz });
-                    world.push(Sphere::new(center, 0.2, Arc::clone(&sphere_material)));
+                    let roughness = utils::rand_float_range(0., 0.5);
+                    sphere_material = Arc::new(Metal::from_albedo(albedo, roughness));
+                    world.push(Arc::new(Sphere::new(
+                        center,
+                        0.2,
+                        Arc::clone(&sphere_material),
+                    )));
                 } else {
                     // Dialectric/Glass
-                    sphere_material = Arc::new(Dialectric {
-                        refrecation_index: 1.5,
-                    });
-                    world.push(Sphere::new(center, 0.2, Arc::clone(&sphere_material)));
+                    sphere_material = Arc::new(Dialectric::new(1.5));
+                    world.push(Arc::new(Sphere::new(
+                        center,
+                        0.2,
+                        Arc::clone(&sphere_material),
+                    )));
                 }
             }
         }
     }
 
-    let mat: Arc<dyn Material> = Arc::new(Dialectric {
-        refrecation_index: 1.5,
-    });
-    world.push(Sphere::new(Point3::from_floats(0., 1., 0.), 1., mat));
-
-    let mat: Arc<dyn Material> = Arc::new(Lambertian {
-        albedo: Color::from_floats(0.4, 0.2, 0.1),
-    });
-    world.push(Sphere::new(Point3::from_floats(-4., 1., 0.), 1., mat));
-
-    let mat: Arc<dyn Material> = Arc::new(Metal {
-        albedo: Color::from_floats(0.7, 0.6, 0.5),
-        fuzz: 0.,
-    });
-    world.push(Sphere::new(Point3::from_floats(4., 1., 0.), 1., mat));
+    let mat: Arc<dyn Material> = Arc::new(Dialectric::new(1.5));
+    world.push(Arc::new(Sphere::new(
+        Point3::from_floats(0., 1., 0.),
+        1.,
+        mat,
+    )));
+
+    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color::from_floats(0.4, 0.2, 0.1)));
+    world.push(Arc::new(Sphere::new(
+        Point3::from_floats(-4., 1., 0.),
+        1.,
+        mat,
+    )));
+
+    let mat: Arc<dyn Material> =
+        Arc::new(Metal::from_albedo(Color::from_floats(0.7, 0.6, 0.5), 0.));
+    world.push(Arc::new(Sphere::new(
+        Point3::from_floats(4., 1., 0.),
+        1.,
+        mat,
+    )));
 
     let mut cam = Camera::default();
     cam.aspect_ratio = 16. / 9.;
@@ -98,7 +135,17 @@ fn main() {
     cam.defocus_angle = 0.6; // 10, set to 0 to remove the Defocus Blur (DoF)
     cam.focus_dist = 10.; // 3.4
 
-    cam.render(Arc::new(world));
+    // RTIOW_CHECKPOINT=<path> saves progress there and picks it up again
+    // after a crash
+    if let Some(path) = std::env::var_os("RTIOW_CHECKPOINT") {
+        cam.checkpoint = Some(Checkpoint::new(path));
+    }
+
+    let stats = cam
+        .render_with_progress(Arc::new(world), progress::terminal_bar())
+        .expect("Render failed");
+    eprintln!();
+    println!("{}", stats);
 }
 
 pub fn sphere_hit(center: &Point3, radius: f64, r: &Ray) -> f64 {
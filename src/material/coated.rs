@@ -0,0 +1,155 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    hit::Hit,
+    onb::Onb,
+    ray::Ray,
+    sampler::{self, BounceSample},
+    utils,
+    vec3::{Color, Vec3},
+};
+
+use super::{beer_lambert, microfacet, Material, Scatter};
+
+/// A dielectric coating over any other material (varnish, car paint clear
+/// coat). Light either reflects off the coating or refracts into it, bounces
+/// off the base material and has to make it back out, possibly reflecting
+/// inside the coating several times. Each of those is decided stochastically,
+/// the coating itself is infinitely thin apart from its absorption.
+pub struct Coated {
+    pub base: Arc<dyn Material>,
+    pub ior: f64,
+    pub roughness: f64,
+    /// Absorption coefficient inside the coating, see `dialectric::absorption_from_transmittance`
+    pub absorption: Color,
+    pub thickness: f64,
+    /// Paths still stuck inside the coating after this many base events are dropped
+    pub max_internal_bounces: usize,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, ior: f64, roughness: f64) -> Self {
+        return Self {
+            base,
+            ior,
+            roughness,
+            absorption: Color::default(),
+            thickness: 0.,
+            max_internal_bounces: 8,
+        };
+    }
+
+    fn absorption_along(&self, cos: f64) -> Color {
+        return beer_lambert(&self.absorption, self.thickness / cos.abs().max(1e-4));
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let frame = Onb::from_w(&hit.shading_normal);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        if wo.2 <= 0. {
+            return None;
+        }
+
+        // Top interface, rough reflection or refraction into the coating
+        let (ax, ay) = microfacet::alpha(self.roughness, 0.);
+        let (u1, u2) = hit.sample.direction;
+        let m = microfacet::sample_visible_normal(&wo, ax, ay, u1, u2);
+        let shadowing = |wi: &Vec3| {
+            if ax < microfacet::MIN_ALPHA {
+                1.
+            } else {
+                microfacet::g2(&wo, wi, ax, ay) / microfacet::g1(&wo, ax, ay)
+            }
+        };
+
+        let cos_i = wo.dot(&m);
+        if hit.sample.lobe < microfacet::fresnel_dielectric(cos_i, self.ior) {
+            let wi = microfacet::reflect(&wo, &m);
+            if wi.2 <= 0. {
+                return None;
+            }
+            return Some(Scatter {
+                attenuation: Color::from_floats(1., 1., 1.).mul(shadowing(&wi)),
+                ray: Ray::new(hit.p, frame.to_world(&wi)),
+                interior: None,
+            });
+        }
+
+        let cos_t = (1. - (1. - cos_i * cos_i) / (self.ior * self.ior))
+            .max(0.)
+            .sqrt();
+        let mut down = wo.inv().div(self.ior) + m.mul(cos_i / self.ior - cos_t);
+        if down.2 >= 0. {
+            return None;
+        }
+
+        let mut throughput = Color::from_floats(1., 1., 1.).mul(shadowing(&down));
+        let mut base_ray = Ray::new(ray_incomming.origin, Vec3::default());
+        base_ray.wavelength = ray_incomming.wavelength;
+        // The top interface used up the camera's samples
+        let mut base_hit = hit.clone();
+        base_hit.sample = BounceSample::random();
+
+        for _ in 0..self.max_internal_bounces {
+            throughput *= self.absorption_along(down.2);
+
+            base_ray.direction = frame.to_world(&down);
+            let scat = self.base.scatter(&base_ray, &base_hit)?;
+            throughput *= scat.attenuation;
+
+            let up = frame.to_local(&scat.ray.direction.norm());
+            // Went through the base, the coating doesn't matter anymore
+            if up.2 <= 0. {
+                return Some(Scatter {
+                    attenuation: throughput,
+                    ray: scat.ray,
+                    interior: scat.interior,
+                });
+            }
+            throughput *= self.absorption_along(up.2);
+
+            // Smooth exit through the bottom of the coating
+            let cos_up = up.2;
+            let sin2_t = (1. - cos_up * cos_up) * self.ior * self.ior;
+            let exit_fresnel = microfacet::fresnel_dielectric(cos_up, 1. / self.ior);
+            if sin2_t < 1. && utils::rand_float() >= exit_fresnel {
+                let out = up.mul(self.ior) + Vec3(0., 0., (1. - sin2_t).sqrt() - self.ior * cos_up);
+                return Some(Scatter {
+                    attenuation: throughput,
+                    ray: Ray::new(hit.p, frame.to_world(&out)),
+                    interior: None,
+                });
+            }
+
+            // Reflected back down onto the base, which needs fresh samples
+            down = Vec3(up.0, up.1, -up.2);
+            base_hit.sample = BounceSample::random();
+        }
+
+        return None;
+    }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return self.base.is_wavelength_dependent();
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "coated",
+            &[
+                &[
+                    self.base.content_hash(),
+                    self.ior.to_bits(),
+                    self.roughness.to_bits(),
+                ][..],
+                &self.absorption.to_bits(),
+                &[self.thickness.to_bits(), self.max_internal_bounces as u64],
+            ]
+            .concat(),
+        );
+    }
+}
@@ -0,0 +1,46 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{hit::Hit, ray::Ray, sampler, texture::Texture, vec3::Color};
+
+use super::{Material, Scatter};
+
+/// Alpha masking. Where `opacity` (first channel) is below one, rays have a
+/// chance to continue straight through as if the surface wasn't there. Such a
+/// pass still counts as a bounce.
+pub struct Cutout {
+    pub base: Arc<dyn Material>,
+    pub opacity: Arc<dyn Texture>,
+}
+
+impl Material for Cutout {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let opacity = self.opacity.value(hit).0.clamp(0., 1.);
+        let (opaque, sample) = hit.sample.pick(opacity);
+        if opaque {
+            let hit = Hit {
+                sample,
+                ..hit.clone()
+            };
+            return self.base.scatter(ray_incomming, &hit);
+        }
+
+        return Some(Scatter {
+            attenuation: Color::from_floats(1., 1., 1.),
+            ray: Ray::new(hit.p, ray_incomming.direction),
+            interior: None,
+        });
+    }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return self.base.is_wavelength_dependent();
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "cutout",
+            &[self.base.content_hash(), self.opacity.content_hash()],
+        );
+    }
+}
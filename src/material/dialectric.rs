@@ -1,17 +1,59 @@
+#![allow(dead_code)]
+
 use crate::{
     hit::Hit,
     ray::Ray,
-    utils,
+    sampler,
+    spectrum::Dispersion,
     vec3::{Color, Vec3},
 };
 
 use super::{Material, Scatter};
 
+pub fn absorption_from_transmittance(transmittance: Color, distance: f64) -> Color {
+    let channel = |t: f64| -t.clamp(1e-6, 1.).ln() / distance;
+    return Color::from_floats(
+        channel(transmittance.0),
+        channel(transmittance.1),
+        channel(transmittance.2),
+    );
+}
+
 pub struct Dialectric {
     pub refrecation_index: f64,
+    /// Absorption coefficient per unit of distance traveled inside the medium
+    pub absorption: Color,
+    /// Replaces `refrecation_index` for rays that carry a wavelength
+    pub dispersion: Option<Dispersion>,
 }
 
 impl Dialectric {
+    pub fn new(refraction_index: f64) -> Self {
+        return Self {
+            refrecation_index: refraction_index,
+            absorption: Color::default(),
+            dispersion: None,
+        };
+    }
+
+    /// Colored glass that lets `transmittance` through after `distance` units.
+    pub fn tinted(refraction_index: f64, transmittance: Color, distance: f64) -> Self {
+        return Self {
+            refrecation_index: refraction_index,
+            absorption: absorption_from_transmittance(transmittance, distance),
+            dispersion: None,
+        };
+    }
+
+    /// Glass that splits light up in spectral mode. Falls back to the index of
+    /// refraction at 550nm otherwise.
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        return Self {
+            dispersion: Some(dispersion),
+            ..Self::new(dispersion.ior(550.))
+        };
+    }
+
     fn reflectance(cos: f64, refraction_index: f64) -> f64 {
         let r0 = (1. - refraction_index) / (1. + refraction_index);
         let r0 = r0 * r0;
@@ -21,29 +63,52 @@ impl Dialectric {
 
 impl Material for Dialectric {
     fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
-        let attenuation = Color::from_floats(1., 1., 1.);
-        let refraction_index = if hit.front_face {
-            1. / self.refrecation_index
-        } else {
-            self.refrecation_index
+        let ior = match (self.dispersion, ray_incomming.wavelength) {
+            (Some(dispersion), Some(lambda)) => dispersion.ior(lambda),
+            _ => self.refrecation_index,
         };
+        let refraction_index = if hit.front_face { 1. / ior } else { ior };
 
         let unit_dir = ray_incomming.direction.norm();
 
-        let cos_theta = unit_dir.inv().dot(&hit.normal).min(1.);
+        let cos_theta = unit_dir.inv().dot(&hit.shading_normal).min(1.);
         let sin_theta = (1. - cos_theta * cos_theta).sqrt();
 
         let refracted = if refraction_index * sin_theta > 1.
-            || Self::reflectance(cos_theta, refraction_index) > utils::rand_float()
+            || Self::reflectance(cos_theta, refraction_index) > hit.sample.lobe
         {
-            Vec3::reflect(&unit_dir, &hit.normal)
+            Vec3::reflect(&unit_dir, &hit.shading_normal)
         } else {
-            Vec3::refract(&unit_dir, &hit.normal, refraction_index)
+            Vec3::refract(&unit_dir, &hit.shading_normal, refraction_index)
         };
 
         return Some(Scatter {
-            attenuation,
+            attenuation: Color::from_floats(1., 1., 1.),
             ray: Ray::new(hit.p, refracted),
+            interior: Some(self.absorption),
         });
     }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return self.dispersion.is_some();
+    }
+
+    fn content_hash(&self) -> u64 {
+        let dispersion = match self.dispersion {
+            None => vec![0],
+            Some(Dispersion::Cauchy { a, b }) => vec![1, a.to_bits(), b.to_bits()],
+            Some(Dispersion::Sellmeier { b, c }) => {
+                [&[2][..], &b.map(f64::to_bits), &c.map(f64::to_bits)].concat()
+            }
+        };
+        return sampler::hash_named(
+            "dialectric",
+            &[
+                &[self.refrecation_index.to_bits()][..],
+                &self.absorption.to_bits(),
+                &dispersion,
+            ]
+            .concat(),
+        );
+    }
 }
@@ -0,0 +1,33 @@
+use core::f64;
+
+use crate::{
+    hit::Hit,
+    ray::Ray,
+    sampler,
+    vec3::{Color, Vec3},
+};
+
+use super::{Material, Scatter};
+
+/// Phase function of a participating medium, scatters uniformly in all directions.
+pub struct Isotropic {
+    pub albedo: Color,
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let (u, v) = hit.sample.direction;
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * f64::consts::PI * v;
+        return Some(Scatter {
+            attenuation: self.albedo,
+            ray: Ray::new(hit.p, Vec3(r * phi.cos(), r * phi.sin(), z)),
+            interior: None,
+        });
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named("isotropic", &self.albedo.to_bits());
+    }
+}
@@ -1,36 +1,40 @@
-use crate::{
-    hit::Hit,
-    ray::Ray,
-    vec3::{Color, Vec3},
-};
+use std::sync::Arc;
+
+use crate::{hit::Hit, onb::Onb, ray::Ray, sampler, texture::Texture, vec3::Color};
 
 use super::{Material, Scatter};
 
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        return Self {
+            albedo: Arc::new(albedo),
+        };
+    }
 }
 
 impl Material for Lambertian {
     fn scatter(&self, _ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
-        let scatter_dir = hit.normal + Vec3::random_on_unit_sphere();
-
-        let scatter_dir = if scatter_dir.is_near_zero() {
-            hit.normal
-        } else {
-            scatter_dir
-        };
+        let scatter_dir = Onb::from_w(&hit.shading_normal)
+            .to_world(&super::cosine_direction(hit.sample.direction));
 
         return Some(Scatter {
-            attenuation: self.albedo,
+            attenuation: self.albedo.value(hit),
             ray: Ray::new(hit.p, scatter_dir),
+            interior: None,
         });
     }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named("lambertian", &[self.albedo.content_hash()]);
+    }
 }
 
 impl Default for Lambertian {
     fn default() -> Self {
-        return Self {
-            albedo: Color::default(),
-        };
+        return Self::new(Color::default());
     }
 }
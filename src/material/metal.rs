@@ -1,38 +1,117 @@
-use crate::{
-    hit::Hit,
-    ray::Ray,
-    vec3::{Color, Vec3},
-};
+#![allow(dead_code)]
 
-use super::{Material, Scatter};
+use crate::{hit::Hit, onb::Onb, ray::Ray, sampler, vec3::Color};
 
+use super::{microfacet, Material, Scatter};
+
+/// GGX microfacet conductor with a complex index of refraction `eta + i*k`
+/// per color channel. `anisotropy` in `[0, 1]` stretches the highlight along
+/// the surface tangent `dpdu`.
 pub struct Metal {
-    pub albedo: Color,
-    pub fuzz: f64,
+    pub eta: Color,
+    pub k: Color,
+    pub roughness: f64,
+    pub anisotropy: f64,
+}
+
+impl Metal {
+    /// Picks `k` so that the reflectance at normal incidence equals `albedo`.
+    pub fn from_albedo(albedo: Color, roughness: f64) -> Self {
+        let k = |r: f64| {
+            let r = r.clamp(0., 0.9999);
+            2. * (r / (1. - r)).sqrt()
+        };
+        return Self {
+            eta: Color::from_floats(1., 1., 1.),
+            k: Color::from_floats(k(albedo.0), k(albedo.1), k(albedo.2)),
+            roughness,
+            anisotropy: 0.,
+        };
+    }
+
+    // Measured values at roughly 650nm, 550nm and 450nm.
+
+    pub fn gold(roughness: f64) -> Self {
+        return Self::preset((0.143, 0.374, 1.442), (3.983, 2.385, 1.603), roughness);
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        return Self::preset((0.155, 0.117, 0.138), (4.828, 3.122, 2.147), roughness);
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        return Self::preset((0.200, 0.924, 1.102), (3.912, 2.452, 2.142), roughness);
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        return Self::preset((1.657, 0.880, 0.521), (9.224, 6.270, 4.837), roughness);
+    }
+
+    pub fn chromium(roughness: f64) -> Self {
+        return Self::preset((3.107, 3.181, 2.323), (3.331, 3.329, 3.135), roughness);
+    }
+
+    fn preset(eta: (f64, f64, f64), k: (f64, f64, f64), roughness: f64) -> Self {
+        return Self {
+            eta: Color::from_tup(eta),
+            k: Color::from_tup(k),
+            roughness,
+            anisotropy: 0.,
+        };
+    }
 }
 
 impl Material for Metal {
+    // Samples visible normals, which leaves `F * G2 / G1` as the path weight.
     fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
-        let reflected = Vec3::reflect(&ray_incomming.direction, &hit.normal);
-        let reflected = reflected.norm() + Vec3::random_on_unit_sphere().mul(self.fuzz);
+        let frame = Onb::from_normal_tangent(&hit.shading_normal, &hit.dpdu);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        if wo.2 <= 0. {
+            return None;
+        }
 
-        let scattered = Ray::new(hit.p, reflected);
-        if scattered.direction.dot(&hit.normal) <= 0.0 {
+        let (ax, ay) = microfacet::alpha(self.roughness, self.anisotropy);
+        let m = microfacet::sample_visible_normal(
+            &wo,
+            ax,
+            ay,
+            hit.sample.direction.0,
+            hit.sample.direction.1,
+        );
+        let wi = microfacet::reflect(&wo, &m);
+        if wi.2 <= 0. {
             return None;
         }
 
+        let fresnel = microfacet::fresnel_conductor(wo.dot(&m), &self.eta, &self.k);
+        let shadowing = if ax < microfacet::MIN_ALPHA && ay < microfacet::MIN_ALPHA {
+            1.
+        } else {
+            microfacet::g2(&wo, &wi, ax, ay) / microfacet::g1(&wo, ax, ay)
+        };
+
         return Some(Scatter {
-            ray: scattered,
-            attenuation: self.albedo,
+            ray: Ray::new(hit.p, frame.to_world(&wi)),
+            attenuation: fresnel.mul(shadowing),
+            interior: None,
         });
     }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "metal",
+            &[
+                &self.eta.to_bits()[..],
+                &self.k.to_bits(),
+                &[self.roughness.to_bits(), self.anisotropy.to_bits()],
+            ]
+            .concat(),
+        );
+    }
 }
 
 impl Default for Metal {
     fn default() -> Self {
-        return Self {
-            albedo: Color::default(),
-            fuzz: 0.,
-        };
+        return Self::from_albedo(Color::default(), 0.);
     }
 }
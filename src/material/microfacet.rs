@@ -0,0 +1,127 @@
+#![allow(dead_code)]
+
+//! GGX/Trowbridge-Reitz microfacet helpers. Directions are in a local frame
+//! with the macro surface normal as z-axis.
+
+use core::f64;
+
+use crate::vec3::{Color, Vec3};
+
+/// Below this the distribution is treated as a perfect mirror.
+pub const MIN_ALPHA: f64 = 1e-4;
+
+/// Maps perceptual roughness and anisotropy in `[0, 1]` to `(alpha_x, alpha_y)`.
+pub fn alpha(roughness: f64, anisotropy: f64) -> (f64, f64) {
+    let a = roughness.clamp(0., 1.).powi(2);
+    let aspect = (1. - 0.9 * anisotropy.clamp(0., 1.)).sqrt();
+    return (a / aspect, a * aspect);
+}
+
+/// Normal distribution function `D(m)`.
+pub fn distribution(m: &Vec3, ax: f64, ay: f64) -> f64 {
+    if m.2 <= 0. {
+        return 0.;
+    }
+    let t = (m.0 / ax).powi(2) + (m.1 / ay).powi(2) + m.2 * m.2;
+    return 1. / (f64::consts::PI * ax * ay * t * t);
+}
+
+/// Smith `Λ(w)` for the anisotropic GGX distribution.
+pub fn lambda(w: &Vec3, ax: f64, ay: f64) -> f64 {
+    let cos2 = w.2 * w.2;
+    if cos2 <= 0. {
+        return f64::INFINITY;
+    }
+    let tan2 = ((w.0 * ax).powi(2) + (w.1 * ay).powi(2)) / cos2;
+    return ((1. + tan2).sqrt() - 1.) / 2.;
+}
+
+pub fn g1(w: &Vec3, ax: f64, ay: f64) -> f64 {
+    return 1. / (1. + lambda(w, ax, ay));
+}
+
+/// Height correlated masking-shadowing.
+pub fn g2(wo: &Vec3, wi: &Vec3, ax: f64, ay: f64) -> f64 {
+    return 1. / (1. + lambda(wo, ax, ay) + lambda(wi, ax, ay));
+}
+
+/// Samples a microfacet normal visible from `wo` (Heitz 2018,
+/// "Sampling the GGX Distribution of Visible Normals").
+pub fn sample_visible_normal(wo: &Vec3, ax: f64, ay: f64, u1: f64, u2: f64) -> Vec3 {
+    if ax < MIN_ALPHA && ay < MIN_ALPHA {
+        return Vec3(0., 0., 1.);
+    }
+
+    // Stretch into the hemisphere configuration
+    let vh = Vec3(ax * wo.0, ay * wo.1, wo.2).norm();
+
+    let len_sq = vh.0 * vh.0 + vh.1 * vh.1;
+    let t1 = if len_sq > 0. {
+        Vec3(-vh.1, vh.0, 0.).div(len_sq.sqrt())
+    } else {
+        Vec3(1., 0., 0.)
+    };
+    let t2 = vh.cross(&t1);
+
+    let r = u1.sqrt();
+    let phi = 2. * f64::consts::PI * u2;
+    let p1 = r * phi.cos();
+    let s = 0.5 * (1. + vh.2);
+    let p2 = (1. - s) * (1. - p1 * p1).sqrt() + s * r * phi.sin();
+    let p3 = (1. - p1 * p1 - p2 * p2).max(0.).sqrt();
+
+    let nh = t1.mul(p1) + t2.mul(p2) + vh.mul(p3);
+
+    // Unstretch
+    return Vec3(ax * nh.0, ay * nh.1, nh.2.max(1e-6)).norm();
+}
+
+/// Mirrors `wo` about `m`.
+pub fn reflect(wo: &Vec3, m: &Vec3) -> Vec3 {
+    return m.mul(2. * wo.dot(m)) - *wo;
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with complex index of
+/// refraction `eta + i*k`, evaluated per color channel.
+pub fn fresnel_conductor(cos_i: f64, eta: &Color, k: &Color) -> Color {
+    let channel = |eta: f64, k: f64| {
+        let cos2 = cos_i.clamp(0., 1.).powi(2);
+        let sin2 = 1. - cos2;
+        let eta2 = eta * eta;
+        let k2 = k * k;
+
+        let t0 = eta2 - k2 - sin2;
+        let a2b2 = (t0 * t0 + 4. * eta2 * k2).sqrt();
+        let t1 = a2b2 + cos2;
+        let a = (0.5 * (a2b2 + t0)).max(0.).sqrt();
+        let t2 = 2. * cos_i * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        0.5 * (rp + rs)
+    };
+
+    return Color::from_floats(
+        channel(eta.0, k.0),
+        channel(eta.1, k.1),
+        channel(eta.2, k.2),
+    );
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric interface, `eta` is the
+/// ratio of the index of refraction behind the surface over the one in front.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1. {
+        return 1.;
+    }
+
+    let cos_t = (1. - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    return 0.5 * (rs * rs + rp * rp);
+}
@@ -0,0 +1,46 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{hit::Hit, ray::Ray, sampler, texture::Texture};
+
+use super::{Material, Scatter};
+
+/// Picks `b` with a probability of `weight` (first channel) and `a` otherwise,
+/// blending the two materials on average.
+pub struct Mix {
+    pub a: Arc<dyn Material>,
+    pub b: Arc<dyn Material>,
+    pub weight: Arc<dyn Texture>,
+}
+
+impl Material for Mix {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let weight = self.weight.value(hit).0.clamp(0., 1.);
+        let (pick_b, sample) = hit.sample.pick(weight);
+        let hit = Hit {
+            sample,
+            ..hit.clone()
+        };
+        return if pick_b {
+            self.b.scatter(ray_incomming, &hit)
+        } else {
+            self.a.scatter(ray_incomming, &hit)
+        };
+    }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return self.a.is_wavelength_dependent() || self.b.is_wavelength_dependent();
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "mix",
+            &[
+                self.a.content_hash(),
+                self.b.content_hash(),
+                self.weight.content_hash(),
+            ],
+        );
+    }
+}
@@ -1,14 +1,64 @@
-use crate::{hit::Hit, ray::Ray, vec3::Color};
+use crate::{
+    hit::Hit,
+    ray::Ray,
+    sampler,
+    vec3::{Color, Vec3},
+};
 
+pub mod coated;
+pub mod cutout;
 pub mod dialectric;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_mapped;
+pub mod oren_nayar;
+pub mod principled;
+pub mod rough_dialectric;
+pub mod subsurface;
+pub mod thin_film;
+pub mod velvet;
 
 pub struct Scatter {
     pub attenuation: Color,
     pub ray: Ray,
+    /// Absorption coefficient of the medium behind the surface, for materials
+    /// that let light into an absorbing interior. When the ray crosses the
+    /// surface the camera attenuates every segment it travels in there, see
+    /// `Ray::medium`. Part of the scatter so that a `Mix` reports the child
+    /// that actually scattered.
+    pub interior: Option<Color>,
 }
 
 pub trait Material: Send + Sync {
     fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter>;
+
+    /// Hash of the parameters, see `Hittable::content_hash`.
+    fn content_hash(&self) -> u64;
+
+    /// Whether scattering depends on `Ray::wavelength`. Spectral rendering
+    /// drops all but the hero wavelength after such an event.
+    fn is_wavelength_dependent(&self) -> bool {
+        return false;
+    }
+}
+
+/// Beer–Lambert transmittance after `distance` units through a medium.
+/// Negative distances count as none, they would turn absorption into gain.
+pub fn beer_lambert(absorption: &Color, distance: f64) -> Color {
+    let distance = distance.max(0.);
+    return Color::from_floats(
+        (-absorption.0 * distance).exp(),
+        (-absorption.1 * distance).exp(),
+        (-absorption.2 * distance).exp(),
+    );
+}
+
+/// Cosine weighted direction around +z from a 2D sample, for materials
+/// working in a local frame.
+fn cosine_direction(u: (f64, f64)) -> Vec3 {
+    let (x, y) = sampler::concentric_disk(u);
+    return Vec3(x, y, (1. - x * x - y * y).max(0.).sqrt());
 }
@@ -0,0 +1,122 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{hit::Hit, onb::Onb, ray::Ray, sampler, texture::Texture, vec3::Vec3};
+
+use super::{Material, Scatter};
+
+/// Offset in texture space for finite differences of bump maps.
+const BUMP_DELTA: f64 = 1e-3;
+
+pub enum NormalPerturbation {
+    /// Tangent space normal map, stored as `(n + 1) / 2`. Load images for it
+    /// with `Image::load_ppm_linear`.
+    NormalMap(Arc<dyn Texture>),
+    /// Scalar height field (first channel), displaces along the normal by
+    /// `height * strength`.
+    Bump {
+        height: Arc<dyn Texture>,
+        strength: f64,
+    },
+}
+
+/// Shades `base` with a perturbed normal. The tangent frame comes from the
+/// hit's `dpdu`/`dpdv`, so the primitive needs a uv parameterization. Mirrored
+/// uvs give a left handed frame, normal maps are read flipped there.
+pub struct NormalMapped {
+    pub base: Arc<dyn Material>,
+    pub perturbation: NormalPerturbation,
+}
+
+impl NormalMapped {
+    fn shading_normal(&self, hit: &Hit) -> Vec3 {
+        let frame = Onb::from_normal_tangent(&hit.shading_normal, &hit.dpdu);
+        let handedness = if hit.shading_normal.cross(&hit.dpdu).dot(&hit.dpdv) < 0. {
+            -1.
+        } else {
+            1.
+        };
+
+        return match &self.perturbation {
+            NormalPerturbation::NormalMap(map) => {
+                let c = map.value(hit);
+                let local = Vec3(2. * c.0 - 1., handedness * (2. * c.1 - 1.), 2. * c.2 - 1.);
+                frame.to_world(&local).norm()
+            }
+            NormalPerturbation::Bump { height, strength } => {
+                let sample = |du: f64, dv: f64| {
+                    let mut shifted = hit.clone();
+                    shifted.u += du;
+                    shifted.v += dv;
+                    height.value(&shifted).0 * strength
+                };
+
+                let h = sample(0., 0.);
+                let dhdu = (sample(BUMP_DELTA, 0.) - h) / BUMP_DELTA;
+                let dhdv = (sample(0., BUMP_DELTA) - h) / BUMP_DELTA;
+
+                // Without a parameterization the arbitrary frame has unit length tangents
+                let (dpdu, dpdv) = if hit.dpdu.is_near_zero() || hit.dpdv.is_near_zero() {
+                    (frame.u, frame.v)
+                } else {
+                    (hit.dpdu, hit.dpdv)
+                };
+
+                let dpdu = dpdu + hit.shading_normal.mul(dhdu);
+                let dpdv = dpdv + hit.shading_normal.mul(dhdv);
+                let n = dpdu.cross(&dpdv).norm();
+
+                // The cross product doesn't care which side the hit came from
+                if n.dot(&hit.shading_normal) < 0. {
+                    n.inv()
+                } else {
+                    n
+                }
+            }
+        };
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let wo = ray_incomming.direction.norm().inv();
+        let mut shading_normal = self.shading_normal(hit);
+
+        // Perturbed normals facing away from the viewer would make the base
+        // material see the surface from behind
+        if shading_normal.dot(&wo) <= 0. {
+            shading_normal = hit.shading_normal;
+        }
+
+        let mut shaded = hit.clone();
+        shaded.shading_normal = shading_normal;
+        let scat = self.base.scatter(ray_incomming, &shaded)?;
+
+        // A direction on different sides of the shading and geometric surface
+        // would leak light through it, or find none where it should
+        let wi = scat.ray.direction;
+        if (wi.dot(&hit.normal) > 0.) != (wi.dot(&shading_normal) > 0.) {
+            return None;
+        }
+
+        return Some(scat);
+    }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return self.base.is_wavelength_dependent();
+    }
+
+    fn content_hash(&self) -> u64 {
+        let perturbation = match &self.perturbation {
+            NormalPerturbation::NormalMap(map) => [0, map.content_hash(), 0],
+            NormalPerturbation::Bump { height, strength } => {
+                [1, height.content_hash(), strength.to_bits()]
+            }
+        };
+        return sampler::hash_named(
+            "normal_mapped",
+            &[&[self.base.content_hash()][..], &perturbation].concat(),
+        );
+    }
+}
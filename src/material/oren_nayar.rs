@@ -0,0 +1,70 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{hit::Hit, onb::Onb, ray::Ray, sampler, texture::Texture, vec3::Color};
+
+use super::{Material, Scatter};
+
+/// Rough diffuse surface (Oren–Nayar, qualitative model). Microfacets that are
+/// Lambertian themselves make clay, plaster or concrete look flatter than
+/// `Lambertian` and scatter more light back towards the viewer.
+pub struct OrenNayar {
+    pub albedo: Arc<dyn Texture>,
+    /// Standard deviation of the facet angles in radians (first channel),
+    /// 0 is `Lambertian`
+    pub sigma: Arc<dyn Texture>,
+}
+
+impl OrenNayar {
+    pub fn new(albedo: Color, sigma: f64) -> Self {
+        return Self {
+            albedo: Arc::new(albedo),
+            sigma: Arc::new(Color::from_floats(sigma, sigma, sigma)),
+        };
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let frame = Onb::from_w(&hit.shading_normal);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        let wi = super::cosine_direction(hit.sample.direction);
+
+        let sigma2 = self.sigma.value(hit).0.powi(2);
+        let a = 1. - sigma2 / (2. * (sigma2 + 0.33));
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        let sin_o = (1. - wo.2 * wo.2).max(0.).sqrt();
+        let sin_i = (1. - wi.2 * wi.2).max(0.).sqrt();
+
+        // Only the azimuth difference matters, projected onto the tangent plane
+        let cos_phi = if sin_o > 1e-4 && sin_i > 1e-4 {
+            ((wi.0 * wo.0 + wi.1 * wo.1) / (sin_i * sin_o)).max(0.)
+        } else {
+            0.
+        };
+
+        // alpha is the larger of both polar angles, beta the smaller one
+        let (sin_alpha, tan_beta) = if wi.2.abs() > wo.2.abs() {
+            (sin_o, sin_i / wi.2.abs().max(1e-8))
+        } else {
+            (sin_i, sin_o / wo.2.abs().max(1e-8))
+        };
+
+        // Cosine sampling cancels the cosine and 1/pi of the BRDF
+        let factor = a + b * cos_phi * sin_alpha * tan_beta;
+        return Some(Scatter {
+            attenuation: self.albedo.value(hit).mul(factor),
+            ray: Ray::new(hit.p, frame.to_world(&wi)),
+            interior: None,
+        });
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "oren_nayar",
+            &[self.albedo.content_hash(), self.sigma.content_hash()],
+        );
+    }
+}
@@ -0,0 +1,274 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    hit::Hit,
+    onb::Onb,
+    ray::Ray,
+    sampler::{self, BounceSample},
+    texture::Texture,
+    utils,
+    vec3::{Color, Vec3},
+};
+
+use super::{microfacet, Material, Scatter};
+
+/// Disney style principled material. Every lobe is sampled on its own, one
+/// lobe per scatter event, picked by its estimated contribution. The clearcoat
+/// sits on top and takes energy from everything below it.
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: f64,
+    pub roughness: f64,
+    /// Dielectric reflectivity, `0.5` is the reflectance of `ior`
+    pub specular: f64,
+    pub sheen: f64,
+    pub sheen_color: Color,
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    pub transmission: f64,
+    pub ior: f64,
+}
+
+#[derive(Clone, Copy)]
+enum Lobe {
+    Clearcoat,
+    Metal,
+    Specular,
+    Transmission,
+    Diffuse,
+    Sheen,
+}
+
+const CLEARCOAT_IOR: f64 = 1.5;
+
+fn schlick(f0: f64, cos: f64) -> f64 {
+    return f0 + (1. - f0) * (1. - cos.clamp(0., 1.)).powf(5.);
+}
+
+fn schlick_color(f0: Color, cos: f64) -> Color {
+    let w = (1. - cos.clamp(0., 1.)).powf(5.);
+    return f0 + (Color::from_floats(1., 1., 1.) - f0).mul(w);
+}
+
+fn f0_from_ior(ior: f64) -> f64 {
+    return ((ior - 1.) / (ior + 1.)).powi(2);
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        return Self {
+            base_color: Arc::new(base_color),
+            ..Default::default()
+        };
+    }
+
+    fn specular_f0(&self) -> f64 {
+        return (f0_from_ior(self.ior) * 2. * self.specular).min(1.);
+    }
+
+    /// Reflects `wo` off a visible microfacet sampled with `u`. Returns the
+    /// direction, the facet's cosine with `wo` and `G2 / G1`.
+    fn sample_reflection(wo: &Vec3, roughness: f64, u: (f64, f64)) -> Option<(Vec3, f64, f64)> {
+        let (ax, ay) = microfacet::alpha(roughness, 0.);
+        let m = microfacet::sample_visible_normal(wo, ax, ay, u.0, u.1);
+        let wi = microfacet::reflect(wo, &m);
+        if wi.2 <= 0. {
+            return None;
+        }
+
+        let shadowing = if ax < microfacet::MIN_ALPHA {
+            1.
+        } else {
+            microfacet::g2(wo, &wi, ax, ay) / microfacet::g1(wo, ax, ay)
+        };
+        return Some((wi, wo.dot(&m), shadowing));
+    }
+
+    fn sample_transmission(
+        wo: &Vec3,
+        roughness: f64,
+        eta: f64,
+        u: (f64, f64),
+    ) -> Option<(Vec3, f64)> {
+        let (ax, ay) = microfacet::alpha(roughness, 0.);
+        let m = microfacet::sample_visible_normal(wo, ax, ay, u.0, u.1);
+
+        let cos_i = wo.dot(&m);
+        let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+        if sin2_t >= 1. {
+            return None;
+        }
+        let wi = wo.inv().div(eta) + m.mul(cos_i / eta - (1. - sin2_t).sqrt());
+        if wi.2 >= 0. {
+            return None;
+        }
+
+        let shadowing = if ax < microfacet::MIN_ALPHA {
+            1.
+        } else {
+            microfacet::g2(wo, &wi, ax, ay) / microfacet::g1(wo, ax, ay)
+        };
+        return Some((wi, shadowing));
+    }
+
+    /// Rays leaving the inside of a transmissive object only see the interface.
+    fn scatter_inside(&self, wo: &Vec3, sample: &BounceSample) -> Option<(Vec3, Color)> {
+        let eta = 1. / self.ior;
+        let fresnel = microfacet::fresnel_dielectric(wo.2, eta);
+        let u = sample.direction;
+
+        return if sample.lobe < fresnel {
+            let (wi, _, shadowing) = Self::sample_reflection(wo, self.roughness, u)?;
+            Some((wi, Color::from_floats(1., 1., 1.).mul(shadowing)))
+        } else {
+            let (wi, shadowing) = Self::sample_transmission(wo, self.roughness, eta, u)?;
+            Some((wi, Color::from_floats(1., 1., 1.).mul(shadowing)))
+        };
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let frame = Onb::from_w(&hit.shading_normal);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        if wo.2 <= 0. {
+            return None;
+        }
+
+        if !hit.front_face && self.transmission > 0. {
+            let (wi, attenuation) = self.scatter_inside(&wo, &hit.sample)?;
+            return Some(Scatter {
+                attenuation,
+                ray: Ray::new(hit.p, frame.to_world(&wi)),
+                interior: None,
+            });
+        }
+
+        let base_color = self.base_color.value(hit);
+        let cos = wo.2;
+
+        // Layer weights, each layer only gets what the ones above let through
+        let coat = self.clearcoat * schlick(f0_from_ior(CLEARCOAT_IOR), cos);
+        let below_coat = 1. - coat;
+        let metal = below_coat * self.metallic;
+        let dielectric = below_coat * (1. - self.metallic);
+        let specular_f = schlick(self.specular_f0(), cos);
+        let transmission = dielectric * (1. - specular_f) * self.transmission;
+        let diffuse = dielectric * (1. - specular_f) * (1. - self.transmission);
+        let sheen = dielectric * (1. - self.transmission) * self.sheen;
+
+        let importance = [
+            (Lobe::Clearcoat, coat),
+            (
+                Lobe::Metal,
+                metal * utils::luminance(&schlick_color(base_color, cos)),
+            ),
+            (Lobe::Specular, dielectric * specular_f),
+            (
+                Lobe::Transmission,
+                transmission * utils::luminance(&base_color),
+            ),
+            (Lobe::Diffuse, diffuse * utils::luminance(&base_color)),
+            (
+                Lobe::Sheen,
+                sheen * utils::luminance(&self.sheen_color) * schlick(0., cos),
+            ),
+        ];
+
+        let total: f64 = importance.iter().map(|(_, w)| w.max(0.)).sum();
+        if total <= 0. {
+            return None;
+        }
+
+        let mut pick = hit.sample.lobe * total;
+        let mut chosen = None;
+        for (lobe, weight) in importance {
+            if weight <= 0. {
+                continue;
+            }
+            chosen = Some((lobe, weight));
+            pick -= weight;
+            if pick < 0. {
+                break;
+            }
+        }
+        let (lobe, weight) = chosen?;
+        let probability = weight / total;
+
+        let white = Color::from_floats(1., 1., 1.);
+        let u = hit.sample.direction;
+        let (wi, throughput) = match lobe {
+            Lobe::Clearcoat => {
+                let (wi, cos_m, shadowing) =
+                    Self::sample_reflection(&wo, self.clearcoat_roughness, u)?;
+                let f = self.clearcoat * schlick(f0_from_ior(CLEARCOAT_IOR), cos_m);
+                (wi, white.mul(f * shadowing))
+            }
+            Lobe::Metal => {
+                let (wi, cos_m, shadowing) = Self::sample_reflection(&wo, self.roughness, u)?;
+                (wi, schlick_color(base_color, cos_m).mul(metal * shadowing))
+            }
+            Lobe::Specular => {
+                let (wi, cos_m, shadowing) = Self::sample_reflection(&wo, self.roughness, u)?;
+                let f = schlick(self.specular_f0(), cos_m);
+                (wi, white.mul(dielectric * f * shadowing))
+            }
+            Lobe::Transmission => {
+                let (wi, shadowing) = Self::sample_transmission(&wo, self.roughness, self.ior, u)?;
+                (wi, base_color.mul(transmission * shadowing))
+            }
+            Lobe::Diffuse => (super::cosine_direction(u), base_color.mul(diffuse)),
+            Lobe::Sheen => {
+                // Cosine sampled, the sheen term grows towards grazing half vectors
+                let wi = super::cosine_direction(u);
+                let h = (wi + wo).norm();
+                let f = schlick(0., wi.dot(&h)) * std::f64::consts::PI;
+                (wi, self.sheen_color.mul(sheen * f))
+            }
+        };
+
+        return Some(Scatter {
+            attenuation: throughput.div(probability),
+            ray: Ray::new(hit.p, frame.to_world(&wi)),
+            interior: None,
+        });
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "principled",
+            &[
+                &[self.base_color.content_hash()][..],
+                &[
+                    self.metallic.to_bits(),
+                    self.roughness.to_bits(),
+                    self.specular.to_bits(),
+                ],
+                &[self.sheen.to_bits()],
+                &self.sheen_color.to_bits(),
+                &[self.clearcoat.to_bits(), self.clearcoat_roughness.to_bits()],
+                &[self.transmission.to_bits(), self.ior.to_bits()],
+            ]
+            .concat(),
+        );
+    }
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        return Self {
+            base_color: Arc::new(Color::from_floats(0.8, 0.8, 0.8)),
+            metallic: 0.,
+            roughness: 0.5,
+            specular: 0.5,
+            sheen: 0.,
+            sheen_color: Color::from_floats(1., 1., 1.),
+            clearcoat: 0.,
+            clearcoat_roughness: 0.03,
+            transmission: 0.,
+            ior: 1.5,
+        };
+    }
+}
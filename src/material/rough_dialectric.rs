@@ -0,0 +1,97 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{hit::Hit, onb::Onb, ray::Ray, sampler, texture::Texture, vec3::Color};
+
+use super::{microfacet, Material, Scatter};
+
+/// Frosted glass, a GGX microfacet dielectric that reflects and transmits
+/// (Walter et al. 2007, "Microfacet Models for Refraction through Rough Surfaces").
+/// Roughness is read from the first channel of the texture.
+pub struct RoughDialectric {
+    pub refraction_index: f64,
+    pub roughness: Arc<dyn Texture>,
+    /// Absorption coefficient per unit of distance traveled inside the medium
+    pub absorption: Color,
+}
+
+impl RoughDialectric {
+    pub fn new(refraction_index: f64, roughness: f64) -> Self {
+        return Self {
+            refraction_index,
+            roughness: Arc::new(Color::from_floats(roughness, roughness, roughness)),
+            absorption: Color::default(),
+        };
+    }
+}
+
+impl Material for RoughDialectric {
+    // Samples visible normals, then picks reflection or refraction at the
+    // microfacet by its Fresnel term, leaving `G2 / G1` as the path weight.
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        // Index of refraction behind the surface over the one in front of it
+        let eta = if hit.front_face {
+            self.refraction_index
+        } else {
+            1. / self.refraction_index
+        };
+
+        let frame = Onb::from_w(&hit.shading_normal);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        if wo.2 <= 0. {
+            return None;
+        }
+
+        let (ax, ay) = microfacet::alpha(self.roughness.value(hit).0, 0.);
+        let m = microfacet::sample_visible_normal(
+            &wo,
+            ax,
+            ay,
+            hit.sample.direction.0,
+            hit.sample.direction.1,
+        );
+
+        let cos_i = wo.dot(&m);
+        let wi = if hit.sample.lobe < microfacet::fresnel_dielectric(cos_i, eta) {
+            let wi = microfacet::reflect(&wo, &m);
+            if wi.2 <= 0. {
+                return None;
+            }
+            wi
+        } else {
+            let cos_t = (1. - (1. - cos_i * cos_i) / (eta * eta)).max(0.).sqrt();
+            let wi = wo.inv().div(eta) + m.mul(cos_i / eta - cos_t);
+            if wi.2 >= 0. {
+                return None;
+            }
+            wi
+        };
+
+        let shadowing = if ax < microfacet::MIN_ALPHA {
+            1.
+        } else {
+            microfacet::g2(&wo, &wi, ax, ay) / microfacet::g1(&wo, ax, ay)
+        };
+
+        return Some(Scatter {
+            attenuation: Color::from_floats(shadowing, shadowing, shadowing),
+            ray: Ray::new(hit.p, frame.to_world(&wi)),
+            interior: Some(self.absorption),
+        });
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "rough_dialectric",
+            &[
+                &[
+                    self.refraction_index.to_bits(),
+                    self.roughness.content_hash(),
+                ][..],
+                &self.absorption.to_bits(),
+            ]
+            .concat(),
+        );
+    }
+}
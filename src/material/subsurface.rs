@@ -0,0 +1,175 @@
+#![allow(dead_code)]
+
+use core::f64;
+use std::sync::Arc;
+
+use crate::{
+    hit::Hit,
+    onb::Onb,
+    ray::Ray,
+    sampler::{self, BounceSample},
+    spectrum,
+    texture::Texture,
+    vec3::{Color, Vec3},
+};
+
+use super::{microfacet, Material, Scatter};
+
+/// Translucent material for skin, wax, marble and the like. A smooth dielectric
+/// boundary around a scattering medium, light refracts in and random walks
+/// through the interior until it finds its way out again.
+///
+/// The walk happens one segment at a time: a ray leaving the interior hits the
+/// object's back face, and the distance up to there decides whether the medium
+/// scattered it before. So the object has to be closed, and every step inside
+/// counts against `Camera::max_bounces_per_ray`. Keep `mean_free_path` in
+/// proportion to the object or raise the bounce limit.
+///
+/// Each walk follows a single wavelength, so colored mean free paths don't turn
+/// into fireflies. In RGB mode that's one of the channels, carried on
+/// `Ray::wavelength` while inside.
+pub struct Subsurface {
+    pub ior: f64,
+    /// Color of the medium after many scattering events, roughly what a
+    /// diffuse material with the same look would have as albedo. Evaluated at
+    /// the boundary hit a walk segment ends at.
+    pub albedo: Arc<dyn Texture>,
+    /// Average distance between scattering events per channel, in scene units
+    pub mean_free_path: Color,
+    /// Henyey–Greenstein asymmetry in `(-1, 1)`, positive is forward scattering
+    pub anisotropy: f64,
+}
+
+impl Subsurface {
+    pub fn new(albedo: Color, mean_free_path: Color) -> Self {
+        return Self {
+            ior: 1.4,
+            albedo: Arc::new(albedo),
+            mean_free_path,
+            anisotropy: 0.,
+        };
+    }
+
+    /// Inverts the multiple scattering albedo to the single scattering one
+    /// (Chiang et al. 2016), so `albedo` ends up as the visible color.
+    fn single_scattering_albedo(albedo: f64) -> f64 {
+        let a = albedo.clamp(0., 1.);
+        let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+        return 1. - s * s;
+    }
+
+    fn sample_phase(&self, forward: &Vec3, (u, v): (f64, f64)) -> Vec3 {
+        let g = self.anisotropy.clamp(-0.99, 0.99);
+        let cos_theta = if g.abs() < 1e-3 {
+            1. - 2. * u
+        } else {
+            let s = (1. - g * g) / (1. - g + 2. * g * u);
+            (1. + g * g - s * s) / (2. * g)
+        };
+
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * f64::consts::PI * v;
+        let local = Vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        return Onb::from_w(forward).to_world(&local);
+    }
+
+    /// Reflection or refraction at the smooth boundary, from either side,
+    /// chosen by `sample.lobe`. Returns what's left of the sample.
+    fn interface(&self, unit_dir: &Vec3, hit: &Hit, sample: &BounceSample) -> (Vec3, BounceSample) {
+        let eta = if hit.front_face {
+            self.ior
+        } else {
+            1. / self.ior
+        };
+
+        let cos_i = unit_dir.inv().dot(&hit.shading_normal);
+        let (reflect, rest) = sample.pick(microfacet::fresnel_dielectric(cos_i, eta));
+        if reflect {
+            return (Vec3::reflect(unit_dir, &hit.shading_normal), rest);
+        }
+        return (Vec3::refract(unit_dir, &hit.shading_normal, 1. / eta), rest);
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let unit_dir = ray_incomming.direction.norm();
+        let mut attenuation = Color::from_floats(1., 1., 1.);
+
+        if hit.front_face {
+            let (dir, rest) = self.interface(&unit_dir, hit, &hit.sample);
+            let mut ray = Ray::new(hit.p, dir);
+
+            // Entering in RGB mode, pick the channel for the walk
+            if ray_incomming.wavelength.is_none() && dir.dot(&hit.normal) < 0. {
+                let channel = ((rest.lobe * 3.) as usize).min(2);
+                ray.wavelength = Some(spectrum::RGB_WAVELENGTHS[channel]);
+                attenuation = Color::default();
+                match channel {
+                    0 => attenuation.0 = 3.,
+                    1 => attenuation.1 = 3.,
+                    _ => attenuation.2 = 3.,
+                }
+            }
+
+            return Some(Scatter {
+                attenuation,
+                ray,
+                interior: None,
+            });
+        }
+
+        let lambda = ray_incomming
+            .wavelength
+            .unwrap_or(spectrum::RGB_WAVELENGTHS[1]);
+        let mean_free_path = spectrum::rgb_to_spectral(&self.mean_free_path, lambda).max(1e-6);
+
+        // The ray started inside, at the entry point or the last scattering event
+        let distance = hit.t * ray_incomming.direction.len();
+        let (scattered, rest) = hit.sample.pick(1. - (-distance / mean_free_path).exp());
+        let t = if scattered {
+            -(1. - hit.sample.lobe).ln() * mean_free_path
+        } else {
+            f64::INFINITY
+        };
+
+        let mut ray = if t < distance {
+            let albedo = spectrum::rgb_to_spectral(&self.albedo.value(hit), lambda);
+            let albedo = Self::single_scattering_albedo(albedo);
+            attenuation = Color::from_floats(albedo, albedo, albedo);
+            Ray::new(
+                ray_incomming.origin + unit_dir.mul(t),
+                self.sample_phase(&unit_dir, hit.sample.direction),
+            )
+        } else {
+            Ray::new(hit.p, self.interface(&unit_dir, hit, &rest).0)
+        };
+
+        // Still inside unless the boundary let it through
+        if ray.direction.dot(&hit.normal) > 0. || t < distance {
+            ray.wavelength = ray_incomming.wavelength;
+        }
+
+        return Some(Scatter {
+            attenuation,
+            ray,
+            interior: None,
+        });
+    }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return true;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "subsurface",
+            &[
+                &[self.ior.to_bits(), self.albedo.content_hash()][..],
+                &self.mean_free_path.to_bits(),
+                &[self.anisotropy.to_bits()],
+            ]
+            .concat(),
+        );
+    }
+}
@@ -0,0 +1,305 @@
+#![allow(dead_code)]
+
+use core::f64;
+
+use crate::{
+    hit::Hit,
+    onb::Onb,
+    ray::Ray,
+    sampler,
+    spectrum::RGB_WAVELENGTHS,
+    vec3::{Color, Vec3},
+};
+
+use super::{metal::Metal, microfacet, Material, Scatter};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ThinFilmBase {
+    Dielectric {
+        ior: f64,
+    },
+    /// GGX rough conductor like `Metal`, the film sits on every microfacet.
+    Conductor {
+        eta: Color,
+        k: Color,
+        roughness: f64,
+    },
+}
+
+/// A smooth surface with a thin coating on top. Light reflected at the top and
+/// bottom of the film interferes, which makes the reflectance depend on the
+/// wavelength: soap bubbles (a dielectric base with `ior = 1`), oil slicks and
+/// coated lenses.
+pub struct ThinFilm {
+    /// In nanometers
+    pub thickness: f64,
+    pub film_ior: f64,
+    pub base: ThinFilmBase,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        return Self { re, im };
+    }
+
+    fn real(re: f64) -> Self {
+        return Self { re, im: 0. };
+    }
+
+    /// `e^(i * phi)`
+    fn unit(phi: f64) -> Self {
+        return Self::new(phi.cos(), phi.sin());
+    }
+
+    fn norm_sqr(&self) -> f64 {
+        return self.re * self.re + self.im * self.im;
+    }
+
+    /// Principal square root.
+    fn sqrt(&self) -> Self {
+        let len = self.norm_sqr().sqrt();
+        let re = ((len + self.re) / 2.).max(0.).sqrt();
+        let im = ((len - self.re) / 2.).max(0.).sqrt();
+        return Self::new(re, if self.im < 0. { -im } else { im });
+    }
+}
+
+impl std::ops::Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Self) -> Self::Output {
+        return Complex::new(self.re + rhs.re, self.im + rhs.im);
+    }
+}
+
+impl std::ops::Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Self) -> Self::Output {
+        return Complex::new(self.re - rhs.re, self.im - rhs.im);
+    }
+}
+
+impl std::ops::Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Self) -> Self::Output {
+        return Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        );
+    }
+}
+
+impl std::ops::Div for Complex {
+    type Output = Complex;
+    fn div(self, rhs: Self) -> Self::Output {
+        let d = rhs.norm_sqr();
+        return Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / d,
+            (self.im * rhs.re - self.re * rhs.im) / d,
+        );
+    }
+}
+
+impl ThinFilm {
+    pub fn soap_bubble(thickness: f64) -> Self {
+        return Self {
+            thickness,
+            film_ior: 1.33,
+            base: ThinFilmBase::Dielectric { ior: 1. },
+        };
+    }
+
+    /// A film on top of `metal`, e.g. `ThinFilm::over_metal(300., 1.5, &Metal::gold(0.1))`
+    /// for heat tinted or anodized metal. Anisotropy isn't supported.
+    pub fn over_metal(thickness: f64, film_ior: f64, metal: &Metal) -> Self {
+        return Self {
+            thickness,
+            film_ior,
+            base: ThinFilmBase::Conductor {
+                eta: metal.eta,
+                k: metal.k,
+                roughness: metal.roughness,
+            },
+        };
+    }
+
+    /// Complex index of refraction of a conductor base at `lambda`, taken
+    /// from the color channel the wavelength falls into.
+    fn conductor_ior(eta: &Color, k: &Color, lambda: f64) -> Complex {
+        let [r, g, b] = RGB_WAVELENGTHS;
+        let pick = |c: &Color| {
+            if lambda >= (r + g) / 2. {
+                c.0
+            } else if lambda >= (g + b) / 2. {
+                c.1
+            } else {
+                c.2
+            }
+        };
+        return Complex::new(pick(eta), pick(k));
+    }
+
+    /// Airy summation of all reflections inside the film, averaged over both
+    /// polarizations. `outside` is the medium the light comes from.
+    fn reflectance(&self, cos_i: f64, lambda: f64, outside: f64, base: Complex) -> f64 {
+        let n1 = Complex::real(outside);
+        let n2 = Complex::real(self.film_ior);
+        let n3 = base;
+
+        let cos1 = Complex::real(cos_i.clamp(0., 1.));
+        let sin2_1 = Complex::real(1. - cos_i * cos_i);
+        let one = Complex::real(1.);
+        let cos2 = (one - (n1 / n2) * (n1 / n2) * sin2_1).sqrt();
+        let cos3 = (one - (n1 / n3) * (n1 / n3) * sin2_1).sqrt();
+
+        let r12_s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+        let r12_p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+        let r23_s = (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3);
+        let r23_p = (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3);
+
+        // Phase difference of one round trip through the film
+        let delta = Complex::real(4. * f64::consts::PI * self.thickness / lambda) * n2 * cos2;
+        let phase = Complex::unit(delta.re) * Complex::real((-delta.im).exp());
+
+        let airy = |r12: Complex, r23: Complex| {
+            ((r12 + r23 * phase) / (one + r12 * r23 * phase))
+                .norm_sqr()
+                .min(1.)
+        };
+
+        return 0.5 * (airy(r12_s, r23_s) + airy(r12_p, r23_p));
+    }
+
+    fn reflectance_color(&self, cos_i: f64, ray: &Ray, outside: f64, base_ior: f64) -> Color {
+        let at = |lambda: f64| {
+            let base = match self.base {
+                ThinFilmBase::Dielectric { .. } => Complex::real(base_ior),
+                ThinFilmBase::Conductor { eta, k, .. } => Self::conductor_ior(&eta, &k, lambda),
+            };
+            self.reflectance(cos_i, lambda, outside, base)
+        };
+
+        // Spectral rendering only follows the hero wavelength through here
+        if let Some(lambda) = ray.wavelength {
+            let r = at(lambda);
+            return Color::from_floats(r, r, r);
+        }
+
+        let [r, g, b] = RGB_WAVELENGTHS;
+        return Color::from_floats(at(r), at(g), at(b));
+    }
+
+    /// Samples visible normals of the rough base like `Metal` does and weighs
+    /// with the film reflectance at the sampled microfacet.
+    fn scatter_conductor(&self, ray_incomming: &Ray, hit: &Hit, roughness: f64) -> Option<Scatter> {
+        let frame = Onb::from_normal_tangent(&hit.shading_normal, &hit.dpdu);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        if wo.2 <= 0. {
+            return None;
+        }
+
+        let (ax, ay) = microfacet::alpha(roughness, 0.);
+        let m = microfacet::sample_visible_normal(
+            &wo,
+            ax,
+            ay,
+            hit.sample.direction.0,
+            hit.sample.direction.1,
+        );
+        let wi = microfacet::reflect(&wo, &m);
+        if wi.2 <= 0. {
+            return None;
+        }
+
+        let shadowing = if ax < microfacet::MIN_ALPHA && ay < microfacet::MIN_ALPHA {
+            1.
+        } else {
+            microfacet::g2(&wo, &wi, ax, ay) / microfacet::g1(&wo, ax, ay)
+        };
+
+        return Some(Scatter {
+            attenuation: self
+                .reflectance_color(wo.dot(&m), ray_incomming, 1., 0.)
+                .mul(shadowing),
+            ray: Ray::new(hit.p, frame.to_world(&wi)),
+            interior: None,
+        });
+    }
+}
+
+impl Material for ThinFilm {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let ior = match self.base {
+            ThinFilmBase::Dielectric { ior } => ior,
+            ThinFilmBase::Conductor { roughness, .. } => {
+                return self.scatter_conductor(ray_incomming, hit, roughness);
+            }
+        };
+
+        let unit_dir = ray_incomming.direction.norm();
+        let cos_i = unit_dir.inv().dot(&hit.shading_normal).clamp(0., 1.);
+        let reflected = Vec3::reflect(&unit_dir, &hit.shading_normal);
+
+        // The film is thin enough to be the same from both sides
+        let (outside, inside) = if hit.front_face { (1., ior) } else { (ior, 1.) };
+        let reflectance = self.reflectance_color(cos_i, ray_incomming, outside, inside);
+        let p_reflect = (reflectance.0 + reflectance.1 + reflectance.2) / 3.;
+
+        let sin_t = outside / inside * (1. - cos_i * cos_i).sqrt();
+        if sin_t >= 1. {
+            return Some(Scatter {
+                attenuation: reflectance,
+                ray: Ray::new(hit.p, reflected),
+                interior: None,
+            });
+        }
+
+        if hit.sample.lobe < p_reflect {
+            return Some(Scatter {
+                attenuation: reflectance.mul(1. / p_reflect),
+                ray: Ray::new(hit.p, reflected),
+                interior: None,
+            });
+        }
+
+        let transmittance = Color::from_floats(1., 1., 1.) - reflectance;
+        return Some(Scatter {
+            attenuation: transmittance.mul(1. / (1. - p_reflect)),
+            ray: Ray::new(
+                hit.p,
+                Vec3::refract(&unit_dir, &hit.shading_normal, outside / inside),
+            ),
+            interior: None,
+        });
+    }
+
+    fn is_wavelength_dependent(&self) -> bool {
+        return true;
+    }
+
+    fn content_hash(&self) -> u64 {
+        let base = match self.base {
+            ThinFilmBase::Dielectric { ior } => vec![0, ior.to_bits()],
+            ThinFilmBase::Conductor { eta, k, roughness } => [
+                &[1][..],
+                &eta.to_bits(),
+                &k.to_bits(),
+                &[roughness.to_bits()],
+            ]
+            .concat(),
+        };
+        return sampler::hash_named(
+            "thin_film",
+            &[
+                &[self.thickness.to_bits(), self.film_ior.to_bits()][..],
+                &base,
+            ]
+            .concat(),
+        );
+    }
+}
@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+
+use core::f64;
+use std::sync::Arc;
+
+use crate::{
+    hit::Hit,
+    onb::Onb,
+    ray::Ray,
+    sampler,
+    texture::Texture,
+    utils,
+    vec3::{Color, Vec3},
+};
+
+use super::{Material, Scatter};
+
+/// Cloth: a diffuse base under a sheen lobe from fibers standing up from the
+/// surface, which light up at grazing angles. The sheen uses the "Charlie"
+/// distribution (Estevez and Kulla 2017) with the visibility term by Neubelt
+/// and Pettineo.
+pub struct Velvet {
+    pub base_color: Arc<dyn Texture>,
+    pub sheen_color: Arc<dyn Texture>,
+    /// Roughness of the sheen in `(0, 1]`, small values concentrate it at the rim
+    pub roughness: Arc<dyn Texture>,
+}
+
+impl Velvet {
+    pub fn new(base_color: Color, sheen_color: Color, roughness: f64) -> Self {
+        return Self {
+            base_color: Arc::new(base_color),
+            sheen_color: Arc::new(sheen_color),
+            roughness: Arc::new(Color::from_floats(roughness, roughness, roughness)),
+        };
+    }
+
+    fn charlie(cos_h: f64, alpha: f64) -> f64 {
+        let inv_alpha = 1. / alpha;
+        let sin2_h = (1. - cos_h * cos_h).max(0.);
+        return (2. + inv_alpha) * sin2_h.powf(0.5 * inv_alpha) / (2. * f64::consts::PI);
+    }
+
+    fn visibility(cos_o: f64, cos_i: f64) -> f64 {
+        return 1. / (4. * (cos_i + cos_o - cos_i * cos_o)).max(1e-8);
+    }
+
+    fn uniform_direction(u: (f64, f64)) -> Vec3 {
+        let z = u.0;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * f64::consts::PI * u.1;
+        return Vec3(r * phi.cos(), r * phi.sin(), z);
+    }
+}
+
+impl Material for Velvet {
+    fn scatter(&self, ray_incomming: &Ray, hit: &Hit) -> Option<Scatter> {
+        let frame = Onb::from_w(&hit.shading_normal);
+        let wo = frame.to_local(&ray_incomming.direction.norm().inv());
+        if wo.2 <= 0. {
+            return None;
+        }
+
+        let base_color = self.base_color.value(hit);
+        let sheen_color = self.sheen_color.value(hit);
+        let alpha = self.roughness.value(hit).0.clamp(0.02, 1.);
+
+        // The base is cosine sampled, the sheen uniformly since it peaks where
+        // the cosine is small. Weighting with the combined pdf keeps both cheap.
+        let (base_weight, sheen_weight) = (
+            utils::luminance(&base_color),
+            utils::luminance(&sheen_color),
+        );
+        let p_sheen = if base_weight + sheen_weight > 0. {
+            sheen_weight / (base_weight + sheen_weight)
+        } else {
+            0.5
+        };
+
+        let wi = if hit.sample.lobe < p_sheen {
+            Self::uniform_direction(hit.sample.direction)
+        } else {
+            super::cosine_direction(hit.sample.direction)
+        };
+        if wi.2 <= 0. {
+            return None;
+        }
+
+        let h = (wi + wo).norm();
+        let sheen = Self::charlie(h.2, alpha) * Self::visibility(wo.2, wi.2);
+        let f = base_color.div(f64::consts::PI) + sheen_color.mul(sheen);
+        let pdf = (1. - p_sheen) * wi.2 / f64::consts::PI + p_sheen / (2. * f64::consts::PI);
+
+        return Some(Scatter {
+            attenuation: f.mul(wi.2 / pdf),
+            ray: Ray::new(hit.p, frame.to_world(&wi)),
+            interior: None,
+        });
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "velvet",
+            &[
+                self.base_color.content_hash(),
+                self.sheen_color.content_hash(),
+                self.roughness.content_hash(),
+            ],
+        );
+    }
+}
@@ -0,0 +1,260 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    sampler, triangle,
+    vec3::{Color, Point3, Vec3},
+};
+
+pub mod ply;
+
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Indexed triangle mesh. `normals`, `colors` and `uvs` are optional per-vertex
+/// attributes, they're either empty or as long as `positions`.
+pub struct TriangleMesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Color>,
+    pub uvs: Vec<(f64, f64)>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+    bvh: Vec<BvhNode>,
+}
+
+/// Nodes are stored depth first, so the left child of an inner node directly
+/// follows it. Leaves reference a range of `indices`.
+struct BvhNode {
+    bounds: Aabb,
+    kind: BvhKind,
+}
+
+enum BvhKind {
+    Leaf { start: usize, count: usize },
+    Inner { right: usize },
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        colors: Vec<Color>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let mut mesh = Self {
+            positions,
+            normals,
+            colors,
+            uvs,
+            indices,
+            material,
+            bvh: vec![],
+        };
+
+        if !mesh.indices.is_empty() {
+            mesh.build_bvh(0, mesh.indices.len());
+        }
+
+        return mesh;
+    }
+
+    fn triangle_bounds(&self, tri: &[usize; 3]) -> Aabb {
+        let mut bounds = Aabb::empty();
+        for i in tri {
+            bounds.grow(&self.positions[*i]);
+        }
+        return bounds;
+    }
+
+    /// Splits the triangles at the median of the longest axis of their centroids.
+    fn build_bvh(&mut self, start: usize, count: usize) {
+        let mut bounds = Aabb::empty();
+        let mut centroids = Aabb::empty();
+        for tri in &self.indices[start..start + count] {
+            let tri_bounds = self.triangle_bounds(tri);
+            bounds.merge(&tri_bounds);
+            centroids.grow(&tri_bounds.center());
+        }
+
+        let node = self.bvh.len();
+        self.bvh.push(BvhNode {
+            bounds,
+            kind: BvhKind::Leaf { start, count },
+        });
+
+        if count <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        let extent = centroids.extent();
+        let axis = if extent.0 >= extent.1 && extent.0 >= extent.2 {
+            0
+        } else if extent.1 >= extent.2 {
+            1
+        } else {
+            2
+        };
+        let key = |v: Point3| match axis {
+            0 => v.0,
+            1 => v.1,
+            _ => v.2,
+        };
+
+        let mid = count / 2;
+        let positions = &self.positions;
+        let centroid =
+            |tri: &[usize; 3]| key(positions[tri[0]] + positions[tri[1]] + positions[tri[2]]);
+        self.indices[start..start + count]
+            .select_nth_unstable_by(mid, |a, b| centroid(a).total_cmp(&centroid(b)));
+
+        self.build_bvh(start, mid);
+        let right = self.bvh.len();
+        self.build_bvh(start + mid, count - mid);
+        self.bvh[node].kind = BvhKind::Inner { right };
+    }
+
+    fn fill_hit(&self, r: &Ray, tri: &[usize; 3], t: f64, b1: f64, b2: f64, hit: &mut Hit) {
+        let b0 = 1. - b1 - b2;
+        let [i0, i1, i2] = *tri;
+        let (p0, p1, p2) = (self.positions[i0], self.positions[i1], self.positions[i2]);
+
+        // Sides are decided by the flat triangle, vertex normals only shade.
+        // Those win over the winding order when it comes to which side is out.
+        let mut outward_normal = (p1 - p0).cross(&(p2 - p0)).norm();
+        let shading_normal = if self.normals.is_empty() {
+            outward_normal
+        } else {
+            (self.normals[i0].mul(b0) + self.normals[i1].mul(b1) + self.normals[i2].mul(b2)).norm()
+        };
+        if outward_normal.dot(&shading_normal) < 0. {
+            outward_normal = outward_normal.inv();
+        }
+
+        hit.t = t;
+        hit.p = r.at(t);
+        hit.set_face_normal(r, &outward_normal);
+        hit.set_shading_normal(&shading_normal);
+        hit.material = Some(Arc::clone(&self.material));
+
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        // Without texture coordinates the barycentrics are the parameterization
+        (hit.u, hit.v, hit.dpdu, hit.dpdv) = if self.uvs.is_empty() {
+            (b1, b2, e1, e2)
+        } else {
+            let (uv0, uv1, uv2) = (self.uvs[i0], self.uvs[i1], self.uvs[i2]);
+            let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+            let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+            let det = du1 * dv2 - dv1 * du2;
+
+            let (dpdu, dpdv) = if det.abs() < 1e-12 {
+                (e1, e2)
+            } else {
+                (
+                    (e1.mul(dv2) - e2.mul(dv1)).div(det),
+                    (e2.mul(du1) - e1.mul(du2)).div(det),
+                )
+            };
+
+            (
+                uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2,
+                uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2,
+                dpdu,
+                dpdv,
+            )
+        };
+
+        hit.vertex_color = if self.colors.is_empty() {
+            None
+        } else {
+            Some(self.colors[i0].mul(b0) + self.colors[i1].mul(b1) + self.colors[i2].mul(b2))
+        };
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+        if self.bvh.is_empty() {
+            return false;
+        }
+
+        let mut interval = Interval::new(interval.min, interval.max);
+        let mut closest = None;
+        let mut stack = vec![0];
+
+        while let Some(idx) = stack.pop() {
+            let node = &self.bvh[idx];
+            if node.bounds.hit_range(r, &interval).is_none() {
+                continue;
+            }
+
+            match node.kind {
+                BvhKind::Leaf { start, count } => {
+                    for tri in &self.indices[start..start + count] {
+                        let [p0, p1, p2] = tri.map(|i| self.positions[i]);
+                        if let Some((t, b1, b2)) = triangle::intersect(r, &interval, &p0, &p1, &p2)
+                        {
+                            interval.max = t;
+                            closest = Some((tri, t, b1, b2));
+                        }
+                    }
+                }
+                BvhKind::Inner { right } => {
+                    stack.push(right);
+                    stack.push(idx + 1);
+                }
+            }
+        }
+
+        return match closest {
+            Some((tri, t, b1, b2)) => {
+                self.fill_hit(r, tri, t, b1, b2, hit);
+                true
+            }
+            None => false,
+        };
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "triangle_mesh",
+            &[
+                &[self.material.content_hash()][..],
+                &self
+                    .positions
+                    .iter()
+                    .flat_map(|v| v.to_bits())
+                    .collect::<Vec<_>>(),
+                &self
+                    .normals
+                    .iter()
+                    .flat_map(|v| v.to_bits())
+                    .collect::<Vec<_>>(),
+                &self
+                    .colors
+                    .iter()
+                    .flat_map(|v| v.to_bits())
+                    .collect::<Vec<_>>(),
+                &self
+                    .uvs
+                    .iter()
+                    .flat_map(|(u, v)| [u.to_bits(), v.to_bits()])
+                    .collect::<Vec<_>>(),
+                &self
+                    .indices
+                    .iter()
+                    .flatten()
+                    .map(|i| *i as u64)
+                    .collect::<Vec<_>>(),
+            ]
+            .concat(),
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use std::{fs, io, path::Path, sync::Arc};
+
+use crate::{
+    material::Material,
+    vec3::{Color, Point3, Vec3},
+};
+
+use super::TriangleMesh;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "char" | "int8" => Some(Self::I8),
+            "uchar" | "uint8" => Some(Self::U8),
+            "short" | "int16" => Some(Self::I16),
+            "ushort" | "uint16" => Some(Self::U16),
+            "int" | "int32" => Some(Self::I32),
+            "uint" | "uint32" => Some(Self::U32),
+            "float" | "float32" => Some(Self::F32),
+            "double" | "float64" => Some(Self::F64),
+            _ => None,
+        };
+    }
+
+    fn size(&self) -> usize {
+        return match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        };
+    }
+
+    /// Linear color channel from a stored value. Integer colors span the whole
+    /// range of the type and are gamma encoded like every other integer color
+    /// we read, float colors are taken as linear.
+    fn color(&self, value: f64) -> f64 {
+        let scale = match self {
+            Self::I8 | Self::U8 => 255.,
+            Self::I16 | Self::U16 => 65535.,
+            Self::I32 | Self::U32 => u32::MAX as f64,
+            Self::F32 | Self::F64 => return value,
+        };
+        let x = value / scale;
+        return x * x;
+    }
+}
+
+enum Property {
+    Scalar {
+        name: String,
+        ty: ScalarType,
+    },
+    List {
+        name: String,
+        count_ty: ScalarType,
+        item_ty: ScalarType,
+    },
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Body<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    format: Format,
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: ScalarType) -> io::Result<f64> {
+        if self.format == Format::Ascii {
+            while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            let start = self.pos;
+            while self.pos < self.bytes.len() && !self.bytes[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+
+            return String::from_utf8_lossy(&self.bytes[start..self.pos])
+                .parse::<f64>()
+                .map_err(|_| invalid("Invalid number in PLY data"));
+        }
+
+        let size = ty.size();
+        let Some(raw) = self.bytes.get(self.pos..self.pos + size) else {
+            return Err(invalid("Truncated PLY data"));
+        };
+        self.pos += size;
+
+        let mut buf = [0u8; 8];
+        buf[..size].copy_from_slice(raw);
+        if self.format == Format::BinaryBigEndian {
+            buf[..size].reverse();
+        }
+
+        return Ok(match ty {
+            ScalarType::I8 => buf[0] as i8 as f64,
+            ScalarType::U8 => buf[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ScalarType::I32 => i32::from_le_bytes(buf[..4].try_into().unwrap()) as f64,
+            ScalarType::U32 => u32::from_le_bytes(buf[..4].try_into().unwrap()) as f64,
+            ScalarType::F32 => f32::from_le_bytes(buf[..4].try_into().unwrap()) as f64,
+            ScalarType::F64 => f64::from_le_bytes(buf),
+        });
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+}
+
+/// Loads an ASCII or binary (little/big endian) PLY file as a triangle mesh.
+/// Per-vertex normals, colors and texture coordinates are picked up when present,
+/// polygons are fan triangulated.
+pub fn load(path: impl AsRef<Path>, material: Arc<dyn Material>) -> io::Result<TriangleMesh> {
+    let bytes = fs::read(path)?;
+
+    let header_end = bytes
+        .windows(b"end_header".len())
+        .position(|w| w == b"end_header")
+        .ok_or_else(|| invalid("PLY header has no end_header"))?;
+    let body_start = bytes[header_end..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map(|i| header_end + i + 1)
+        .unwrap_or(bytes.len());
+
+    let header = String::from_utf8_lossy(&bytes[..header_end]);
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(invalid("Not a PLY file"));
+    }
+
+    let mut format = None;
+    let mut elements = Vec::<Element>::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", "ascii", ..] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", ..] => format = Some(Format::BinaryLittleEndian),
+            ["format", "binary_big_endian", ..] => format = Some(Format::BinaryBigEndian),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid("Invalid PLY element count"))?,
+                properties: vec![],
+            }),
+            ["property", "list", count_ty, item_ty, name] => {
+                let property = Property::List {
+                    name: name.to_string(),
+                    count_ty: ScalarType::parse(count_ty)
+                        .ok_or_else(|| invalid("Unknown PLY property type"))?,
+                    item_ty: ScalarType::parse(item_ty)
+                        .ok_or_else(|| invalid("Unknown PLY property type"))?,
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("PLY property outside of an element"))?
+                    .properties
+                    .push(property);
+            }
+            ["property", ty, name] => {
+                let property = Property::Scalar {
+                    name: name.to_string(),
+                    ty: ScalarType::parse(ty)
+                        .ok_or_else(|| invalid("Unknown PLY property type"))?,
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("PLY property outside of an element"))?
+                    .properties
+                    .push(property);
+            }
+            _ => {}
+        }
+    }
+
+    let mut body = Body {
+        bytes: &bytes[body_start..],
+        pos: 0,
+        format: format.ok_or_else(|| invalid("PLY header has no format"))?,
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+
+    for element in &elements {
+        let has = |names: &[&str]| {
+            element.properties.iter().any(|p| match p {
+                Property::Scalar { name, .. } => names.contains(&name.as_str()),
+                Property::List { .. } => false,
+            })
+        };
+        let (has_normals, has_colors, has_uvs) = (
+            has(&["nx"]),
+            has(&["red", "diffuse_red"]),
+            has(&["u", "s", "texture_u", "texture_s"]),
+        );
+
+        for _ in 0..element.count {
+            let mut p = Point3::default();
+            let mut n = Vec3::default();
+            let mut c = Color::default();
+            let mut uv = (0., 0.);
+
+            for property in &element.properties {
+                match property {
+                    Property::Scalar { name, ty } => {
+                        let value = body.read(*ty)?;
+                        let color = ty.color(value);
+                        match name.as_str() {
+                            "x" => p.0 = value,
+                            "y" => p.1 = value,
+                            "z" => p.2 = value,
+                            "nx" => n.0 = value,
+                            "ny" => n.1 = value,
+                            "nz" => n.2 = value,
+                            "red" | "diffuse_red" => c.0 = color,
+                            "green" | "diffuse_green" => c.1 = color,
+                            "blue" | "diffuse_blue" => c.2 = color,
+                            "u" | "s" | "texture_u" | "texture_s" => uv.0 = value,
+                            "v" | "t" | "texture_v" | "texture_t" => uv.1 = value,
+                            _ => {}
+                        }
+                    }
+                    Property::List {
+                        name,
+                        count_ty,
+                        item_ty,
+                    } => {
+                        // Not preallocated, the count comes straight from the
+                        // file and reading runs out of data soon enough if it lies
+                        let count = body.read(*count_ty)? as usize;
+                        let mut items = Vec::new();
+                        for _ in 0..count {
+                            items.push(body.read(*item_ty)? as usize);
+                        }
+
+                        let is_face = element.name == "face"
+                            && (name == "vertex_indices" || name == "vertex_index");
+                        if is_face {
+                            for i in 1..items.len().saturating_sub(1) {
+                                indices.push([items[0], items[i], items[i + 1]]);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                positions.push(p);
+                if has_normals {
+                    normals.push(n);
+                }
+                if has_colors {
+                    colors.push(c);
+                }
+                if has_uvs {
+                    uvs.push(uv);
+                }
+            }
+        }
+    }
+
+    if indices.iter().flatten().any(|i| *i >= positions.len()) {
+        return Err(invalid("PLY face references a missing vertex"));
+    }
+
+    return Ok(TriangleMesh::new(
+        positions, normals, colors, uvs, indices, material,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+
+    const HEADER: &str = "element vertex 4\n\
+        property float x\n\
+        property float y\n\
+        property float z\n\
+        property uchar red\n\
+        property uchar green\n\
+        property uchar blue\n\
+        element face 1\n\
+        property list uchar int vertex_indices\n\
+        end_header\n";
+
+    const POSITIONS: [[f32; 3]; 4] = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., -2.5]];
+    const COLORS: [[u8; 3]; 4] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [51, 102, 204]];
+
+    fn load_bytes(name: &str, bytes: &[u8]) -> io::Result<TriangleMesh> {
+        let path = std::env::temp_dir().join(format!("rtiow-{}-{}.ply", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        let mesh = load(
+            &path,
+            Arc::new(Lambertian::new(Color::from_floats(0.5, 0.5, 0.5))),
+        );
+        fs::remove_file(&path).unwrap();
+        return mesh;
+    }
+
+    /// The quad from `HEADER` in binary, with the byte order of the given converters.
+    fn binary(
+        format: &str,
+        f32_bytes: fn(f32) -> [u8; 4],
+        i32_bytes: fn(i32) -> [u8; 4],
+    ) -> Vec<u8> {
+        let mut bytes = format!("ply\nformat {} 1.0\n{}", format, HEADER).into_bytes();
+        for (p, c) in POSITIONS.iter().zip(&COLORS) {
+            for x in p {
+                bytes.extend(f32_bytes(*x));
+            }
+            bytes.extend(c);
+        }
+        bytes.push(4);
+        for i in 0..4 {
+            bytes.extend(i32_bytes(i));
+        }
+        return bytes;
+    }
+
+    fn assert_quad(mesh: &TriangleMesh) {
+        let positions: Vec<[f64; 3]> = mesh.positions.iter().map(|p| [p.0, p.1, p.2]).collect();
+        let expected: Vec<[f64; 3]> = POSITIONS.iter().map(|p| p.map(|x| x as f64)).collect();
+        assert_eq!(positions, expected);
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert!(mesh.normals.is_empty() && mesh.uvs.is_empty());
+
+        for (c, expected) in mesh.colors.iter().zip(&COLORS) {
+            let expected = expected.map(|x| (x as f64 / 255.).powi(2));
+            assert!((c.0 - expected[0]).abs() < 1e-12);
+            assert!((c.1 - expected[1]).abs() < 1e-12);
+            assert!((c.2 - expected[2]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn ascii_quad() {
+        let mut text = format!("ply\nformat ascii 1.0\ncomment a quad\n{}", HEADER);
+        for (p, c) in POSITIONS.iter().zip(&COLORS) {
+            text += &format!("{} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2]);
+        }
+        text += "4 0 1 2 3\n";
+        assert_quad(&load_bytes("ascii", text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_quad_in_both_byte_orders() {
+        let le = binary("binary_little_endian", f32::to_le_bytes, i32::to_le_bytes);
+        assert_quad(&load_bytes("le", &le).unwrap());
+        let be = binary("binary_big_endian", f32::to_be_bytes, i32::to_be_bytes);
+        assert_quad(&load_bytes("be", &be).unwrap());
+    }
+
+    #[test]
+    fn float_colors_are_linear() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 3\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property float red\nproperty float green\nproperty float blue\n\
+            element face 1\nproperty list uchar uint vertex_index\nend_header\n\
+            0 0 0 0.5 0.25 1\n1 0 0 0 0 0\n0 1 0 1 1 1\n3 0 1 2\n";
+        let mesh = load_bytes("float-colors", text.as_bytes()).unwrap();
+        assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+        assert_eq!(
+            (mesh.colors[0].0, mesh.colors[0].1, mesh.colors[0].2),
+            (0.5, 0.25, 1.)
+        );
+    }
+
+    #[test]
+    fn lying_list_count_is_invalid() {
+        let mut bytes = binary("binary_little_endian", f32::to_le_bytes, i32::to_le_bytes);
+        // A face claiming 255 indices while only 4 follow
+        let count = bytes.len() - 17;
+        bytes[count] = 255;
+        let err = load_bytes("lying-count", &bytes)
+            .err()
+            .expect("short face list loads");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn missing_vertex_is_invalid() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 1\n\
+            property float x\nproperty float y\nproperty float z\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+            0 0 0\n3 0 1 2\n";
+        let err = load_bytes("missing-vertex", text.as_bytes())
+            .err()
+            .expect("face out of range loads");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal basis around `w`. Local coordinates have `w` as z-axis.
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: &Vec3) -> Self {
+        let w = n.norm();
+        let a = if w.0.abs() > 0.9 {
+            Vec3(0., 1., 0.)
+        } else {
+            Vec3(1., 0., 0.)
+        };
+        let v = w.cross(&a).norm();
+        let u = v.cross(&w);
+        return Self { u, v, w };
+    }
+
+    /// Basis around `n` with `u` following the tangent `t` as close as possible.
+    /// Falls back to an arbitrary tangent if `t` is zero or parallel to `n`.
+    pub fn from_normal_tangent(n: &Vec3, t: &Vec3) -> Self {
+        let w = n.norm();
+        let u = *t - w.mul(w.dot(t));
+        if u.len_squared() < 1e-16 {
+            return Self::from_w(n);
+        }
+
+        let u = u.norm();
+        let v = w.cross(&u);
+        return Self { u, v, w };
+    }
+
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        return Vec3(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w));
+    }
+
+    pub fn to_world(&self, a: &Vec3) -> Vec3 {
+        return self.u.mul(a.0) + self.v.mul(a.1) + self.w.mul(a.2);
+    }
+}
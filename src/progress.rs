@@ -0,0 +1,170 @@
+#![allow(dead_code)]
+
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Snapshot of a running render, handed to the progress callback after every
+/// finished tile and once more when the render stops, with `done` set. Counts
+/// cover this run only, not samples resumed from a checkpoint.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// Pass in progress, starting at 1
+    pub pass: usize,
+    /// Passes needed to reach the target sample count if nothing stops early
+    pub passes: usize,
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub pixels_done: usize,
+    pub pixels_total: usize,
+    pub samples: u64,
+    /// Rays intersected with the scene, camera rays and every bounce. What
+    /// materials and media trace internally isn't counted: subsurface random
+    /// walks, bounces inside coatings and delta tracking steps.
+    pub rays: u64,
+    pub elapsed: Duration,
+    pub samples_per_second: f64,
+    pub rays_per_second: f64,
+    /// Fraction of all planned passes that is done, between 0 and 1
+    pub fraction: f64,
+    /// Estimated time left. Pessimistic with adaptive sampling or a noise
+    /// threshold, which can stop well before the planned passes.
+    pub eta: Option<Duration>,
+    /// Last call, the render stopped, possibly early
+    pub done: bool,
+}
+
+/// Totals returned once a render is done.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderStats {
+    pub width: usize,
+    pub height: usize,
+    pub elapsed: Duration,
+    pub passes: usize,
+    pub samples: u64,
+    /// Counted like `Progress::rays`
+    pub rays: u64,
+    /// Samples the film already had from a checkpoint
+    pub resumed_samples: u64,
+    /// Over the whole film, resumed samples included
+    pub average_samples_per_pixel: f64,
+    pub mean_error: f64,
+}
+
+impl RenderStats {
+    pub fn samples_per_second(&self) -> f64 {
+        return per_second(self.samples, self.elapsed);
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        return per_second(self.rays, self.elapsed);
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Rendered {}x{} in {} ({} passes)",
+            self.width,
+            self.height,
+            format_duration(self.elapsed),
+            self.passes
+        )?;
+        if self.resumed_samples > 0 {
+            writeln!(
+                f,
+                "  resumed with   {} samples",
+                format_count(self.resumed_samples as f64)
+            )?;
+        }
+        writeln!(
+            f,
+            "  samples        {} ({:.1} per pixel)",
+            format_count(self.samples as f64),
+            self.average_samples_per_pixel
+        )?;
+        writeln!(f, "  rays           {}", format_count(self.rays as f64))?;
+        writeln!(
+            f,
+            "  samples/s      {}",
+            format_count(self.samples_per_second())
+        )?;
+        writeln!(
+            f,
+            "  rays/s         {}",
+            format_count(self.rays_per_second())
+        )?;
+        return write!(f, "  mean error     {:.4}", self.mean_error);
+    }
+}
+
+/// Progress callback drawing a single line bar on stderr, redrawn at most a
+/// few times a second and always on the final call. The line is left open,
+/// end it once the render returns.
+pub fn terminal_bar() -> impl Fn(&Progress) + Sync {
+    let last_draw: Mutex<Option<Instant>> = Mutex::new(None);
+
+    return move |p: &Progress| {
+        {
+            let mut last = last_draw.lock().unwrap();
+            if !p.done && last.is_some_and(|t| t.elapsed() < Duration::from_millis(100)) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        const WIDTH: usize = 30;
+        let filled = ((p.fraction * WIDTH as f64) as usize).min(WIDTH);
+        let eta = match p.eta {
+            Some(eta) => format_duration(eta),
+            None => "?".to_string(),
+        };
+
+        let mut err = io::stderr().lock();
+        let _ = write!(
+            err,
+            "\r[{}{}] {:5.1}%  pass {}/{}  {} samples/s  {} rays/s  ETA {}  \x1b[K",
+            "#".repeat(filled),
+            " ".repeat(WIDTH - filled),
+            100. * p.fraction,
+            p.pass,
+            p.passes,
+            format_count(p.samples_per_second),
+            format_count(p.rays_per_second),
+            eta
+        );
+        let _ = err.flush();
+    };
+}
+
+pub fn per_second(count: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0. {
+        return 0.;
+    }
+    return count as f64 / secs;
+}
+
+/// `1234567` as `1.23M`.
+fn format_count(n: f64) -> String {
+    return match n {
+        n if n >= 1e9 => format!("{:.2}G", n / 1e9),
+        n if n >= 1e6 => format!("{:.2}M", n / 1e6),
+        n if n >= 1e3 => format!("{:.2}k", n / 1e3),
+        n => format!("{:.0}", n),
+    };
+}
+
+/// `1h 02m 03s`, `2m 03s` or `3.4s`.
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    return match secs {
+        s if s >= 3600 => format!("{}h {:02}m {:02}s", s / 3600, s / 60 % 60, s % 60),
+        s if s >= 60 => format!("{}m {:02}s", s / 60, s % 60),
+        _ => format!("{:.1}s", d.as_secs_f64()),
+    };
+}
@@ -1,13 +1,24 @@
-use crate::vec3::{Point3, Vec3};
+use crate::vec3::{Color, Point3, Vec3};
 
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    /// Hero wavelength in nanometers, only set when rendering spectrally or
+    /// inside a `Subsurface` medium
+    pub wavelength: Option<f64>,
+    /// Absorption coefficient of the medium the ray travels through, set by
+    /// the camera when a ray enters a material with an absorbing interior
+    pub medium: Option<Color>,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Ray {
-        return Ray { origin, direction };
+        return Ray {
+            origin,
+            direction,
+            wavelength: None,
+            medium: None,
+        };
     }
     pub fn at(&self, t: f64) -> Point3 {
         return self.origin + self.direction.mul(t);
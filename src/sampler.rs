@@ -0,0 +1,392 @@
+#![allow(dead_code)]
+
+//! Sample generators for the render loop. Every camera sample asks for its
+//! dimensions in the same order: the position inside the pixel (2D), the lens
+//! (2D), the time (1D, reserved), the wavelength in spectral mode (1D) and then
+//! a `BounceSample` per bounce. Samplers other than `Independent` spread those
+//! dimensions evenly over the samples of one pixel.
+
+use crate::utils;
+
+/// Which sampler the camera creates for its workers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SamplerKind {
+    /// Plain uniform random numbers
+    Independent,
+    /// Jittered strata, shuffled independently for every dimension
+    Stratified,
+    /// Halton sequence with Owen scrambled digits per pixel
+    Halton,
+    /// Sobol sequence with hash based Owen scrambling (Burley 2020)
+    Sobol,
+}
+
+impl SamplerKind {
+    /// Samplers with the same `seed` produce the same samples for a pixel,
+    /// no matter which thread asks.
+    pub fn create(&self, samples_per_pixel: usize, seed: u64) -> Box<dyn Sampler> {
+        let state = State {
+            seed,
+            ..Default::default()
+        };
+        return match self {
+            Self::Independent => Box::new(Independent { seed }),
+            Self::Stratified => Box::new(Stratified {
+                samples_per_pixel: samples_per_pixel.max(1),
+                state,
+            }),
+            Self::Halton => Box::new(Halton { state }),
+            Self::Sobol => Box::new(Sobol { state }),
+        };
+    }
+}
+
+pub trait Sampler {
+    /// Restarts the dimensions for sample `index` of pixel `(x, y)`. Also
+    /// reseeds the thread's `utils::rand_float` for everything that doesn't
+    /// go through the sampler.
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize);
+    fn get_1d(&mut self) -> f64;
+    fn get_2d(&mut self) -> (f64, f64);
+
+    fn get_bounce(&mut self) -> BounceSample {
+        return BounceSample {
+            lobe: self.get_1d(),
+            direction: self.get_2d(),
+        };
+    }
+}
+
+/// The dimensions reserved for one scattering event: a number to choose
+/// between lobes and a 2D sample for the direction. The camera stores them in
+/// `Hit::sample`, materials that don't use them draw their own random numbers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BounceSample {
+    pub lobe: f64,
+    pub direction: (f64, f64),
+}
+
+impl BounceSample {
+    pub fn random() -> Self {
+        return Self {
+            lobe: utils::rand_float(),
+            direction: (utils::rand_float(), utils::rand_float()),
+        };
+    }
+
+    /// Takes the first of two choices with `probability`, decided by `lobe`.
+    /// The returned sample has `lobe` stretched back over `[0, 1)` inside the
+    /// chosen part, so a material deciding next still gets a uniform number.
+    pub fn pick(&self, probability: f64) -> (bool, BounceSample) {
+        let first = self.lobe < probability;
+        let lobe = if first {
+            self.lobe / probability
+        } else {
+            (self.lobe - probability) / (1. - probability)
+        };
+        let sample = BounceSample {
+            lobe: lobe.clamp(0., 1. - f64::EPSILON),
+            direction: self.direction,
+        };
+        return (first, sample);
+    }
+}
+
+/// Maps the unit square onto the unit disk, keeping strata intact
+/// (Shirley and Chiu 1997).
+pub fn concentric_disk(u: (f64, f64)) -> (f64, f64) {
+    let (a, b) = (2. * u.0 - 1., 2. * u.1 - 1.);
+    if a == 0. && b == 0. {
+        return (0., 0.);
+    }
+
+    let quarter_pi = std::f64::consts::FRAC_PI_4;
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, quarter_pi * (b / a))
+    } else {
+        (b, 2. * quarter_pi - quarter_pi * (a / b))
+    };
+    return (r * theta.cos(), r * theta.sin());
+}
+
+/// 64 bit finalizer from splitmix64, mixes in one value after another.
+pub fn hash(values: &[u64]) -> u64 {
+    let mut h: u64 = 0x9e3779b97f4a7c15;
+    for v in values {
+        h ^= v.wrapping_add(0x9e3779b97f4a7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+        h ^= h >> 31;
+    }
+    return h;
+}
+
+/// `hash` of `values` behind a name, so different kinds of objects with the
+/// same parameters don't collide.
+pub fn hash_named(name: &str, values: &[u64]) -> u64 {
+    let name = name.bytes().map(u64::from);
+    return hash(&name.chain(values.iter().copied()).collect::<Vec<_>>());
+}
+
+/// Uniform float in `[0, 1)` from the upper bits of a hash.
+fn hash_float(values: &[u64]) -> f64 {
+    return (hash(values) >> 11) as f64 / (1u64 << 53) as f64;
+}
+
+fn to_float(bits: u32) -> f64 {
+    return bits as f64 / 4294967296.;
+}
+
+/// Random numbers for one camera sample, only depending on where it is.
+fn seed_sample(seed: u64, x: usize, y: usize, index: usize) {
+    utils::seed_rng(hash(&[seed, x as u64, y as u64, index as u64]));
+}
+
+/// Pixel, sample index and next dimension, shared by all structured samplers.
+#[derive(Default)]
+struct State {
+    seed: u64,
+    pixel: u64,
+    index: u64,
+    dimension: u64,
+}
+
+impl State {
+    fn start(&mut self, x: usize, y: usize, index: usize) {
+        self.pixel = hash(&[self.seed, x as u64, y as u64]);
+        self.index = index as u64;
+        self.dimension = 0;
+        seed_sample(self.seed, x, y, index);
+    }
+
+    fn next_dimension(&mut self) -> u64 {
+        self.dimension += 1;
+        return self.dimension - 1;
+    }
+}
+
+pub struct Independent {
+    seed: u64,
+}
+
+impl Sampler for Independent {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        seed_sample(self.seed, x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        return utils::rand_float();
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        return (utils::rand_float(), utils::rand_float());
+    }
+}
+
+/// Random permutation of `0..len` selected by `seed` without storing it,
+/// by cycle walking a hash (Kensler 2013, "Correlated Multi-Jittered Sampling").
+fn permute(mut i: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    return (i.wrapping_add(seed)) % len;
+}
+
+/// One stratum per sample in every 1D dimension. 2D dimensions use the
+/// smallest square grid with at least as many cells as samples.
+pub struct Stratified {
+    samples_per_pixel: usize,
+    state: State,
+}
+
+impl Stratified {
+    fn stratum(&self, cells: u32, dimension: u64) -> u32 {
+        let seed = hash(&[self.state.pixel, dimension]) as u32;
+        return permute(self.state.index as u32 % cells, cells, seed);
+    }
+}
+
+impl Sampler for Stratified {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next_dimension();
+        let cells = self.samples_per_pixel as u32;
+        let jitter = hash_float(&[self.state.pixel, self.state.index, dimension]);
+        return (self.stratum(cells, dimension) as f64 + jitter) / cells as f64;
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.next_dimension();
+        let n = (self.samples_per_pixel as f64).sqrt().ceil() as u32;
+        let stratum = self.stratum(n * n, dimension);
+
+        let jitter_x = hash_float(&[self.state.pixel, self.state.index, dimension, 0]);
+        let jitter_y = hash_float(&[self.state.pixel, self.state.index, dimension, 1]);
+        return (
+            ((stratum % n) as f64 + jitter_x) / n as f64,
+            ((stratum / n) as f64 + jitter_y) / n as f64,
+        );
+    }
+}
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Radical inverse per prime base. Dimensions past the prime table fall back
+/// to hashed random numbers, the higher bases wouldn't stratify well anyway.
+pub struct Halton {
+    state: State,
+}
+
+impl Halton {
+    /// Every digit is permuted depending on the digits before it, which breaks
+    /// up the correlation between large bases at low sample counts.
+    fn owen_scrambled_radical_inverse(mut index: u64, base: u64, seed: u64) -> f64 {
+        let inv_base = 1. / base as f64;
+        let mut inv = 1.;
+        let mut prefix: u64 = 0;
+        let mut value = 0.;
+
+        // Trailing zero digits get scrambled too, until doubles run out of precision
+        while inv > 1e-15 {
+            let digit = index % base;
+            index /= base;
+
+            let digit_seed = hash(&[seed, prefix]) as u32;
+            let digit = permute(digit as u32, base as u32, digit_seed) as u64;
+            prefix = prefix.wrapping_mul(base).wrapping_add(digit);
+
+            inv *= inv_base;
+            value += digit as f64 * inv;
+        }
+        return value.min(1. - f64::EPSILON);
+    }
+}
+
+impl Sampler for Halton {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.state.next_dimension();
+        let Some(base) = PRIMES.get(dimension as usize) else {
+            return hash_float(&[self.state.pixel, self.state.index, dimension]);
+        };
+        let seed = hash(&[self.state.pixel, dimension]);
+        return Self::owen_scrambled_radical_inverse(self.state.index, *base, seed);
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        return (self.get_1d(), self.get_1d());
+    }
+}
+
+/// Generator matrices of the first two Sobol dimensions, the van der Corput
+/// sequence and the one from the polynomial `x + 1`.
+const fn sobol_directions() -> [[u32; 32]; 2] {
+    let mut out = [[0; 32]; 2];
+    out[0][0] = 1 << 31;
+    out[1][0] = 1 << 31;
+
+    let mut i = 1;
+    while i < 32 {
+        out[0][i] = 1 << (31 - i);
+        out[1][i] = out[1][i - 1] ^ (out[1][i - 1] >> 1);
+        i += 1;
+    }
+    return out;
+}
+
+const SOBOL_DIRECTIONS: [[u32; 32]; 2] = sobol_directions();
+
+/// Owen scrambled Sobol points. Every 1D or 2D request takes the first
+/// dimensions of its own shuffled and scrambled sequence, which is how Burley
+/// pads a low dimensional sequence to any number of dimensions.
+pub struct Sobol {
+    state: State,
+}
+
+impl Sobol {
+    fn sobol(index: u32, dimension: usize) -> u32 {
+        let mut out = 0;
+        let mut index = index;
+        let mut bit = 0;
+        while index != 0 {
+            if index & 1 != 0 {
+                out ^= SOBOL_DIRECTIONS[dimension][bit];
+            }
+            index >>= 1;
+            bit += 1;
+        }
+        return out;
+    }
+
+    /// Laine and Karras style hash that only lets bits affect higher ones,
+    /// on reversed bits that's an Owen scramble.
+    fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+        let mut x = x.reverse_bits();
+        x = x.wrapping_add(seed);
+        x ^= x.wrapping_mul(0x6c50b47c);
+        x ^= x.wrapping_mul(0xb82f1e52);
+        x ^= x.wrapping_mul(0xc7afe638);
+        x ^= x.wrapping_mul(0x8d22f6e6);
+        return x.reverse_bits();
+    }
+
+    fn sample(&self, set: u64, dimension: usize) -> f64 {
+        let seed = hash(&[self.state.pixel, set]);
+        let index = Self::nested_uniform_scramble(self.state.index as u32, seed as u32);
+        let value = Self::sobol(index, dimension);
+        let value = Self::nested_uniform_scramble(value, hash(&[seed, dimension as u64]) as u32);
+        return to_float(value);
+    }
+}
+
+impl Sampler for Sobol {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let set = self.state.next_dimension();
+        return self.sample(set, 0);
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let set = self.state.next_dimension();
+        return (self.sample(set, 0), self.sample(set, 1));
+    }
+}
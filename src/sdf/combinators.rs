@@ -0,0 +1,103 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{sampler, vec3::Point3};
+
+use super::Sdf;
+
+// Polynomial smooth min/max, `k` is the size of the blend region.
+// https://iquilezles.org/articles/smin/
+
+pub struct SmoothUnion {
+    pub a: Arc<dyn Sdf>,
+    pub b: Arc<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+        if self.k <= 0. {
+            return d1.min(d2);
+        }
+
+        let h = (0.5 + 0.5 * (d2 - d1) / self.k).clamp(0., 1.);
+        return lerp(d2, d1, h) - self.k * h * (1. - h);
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "smooth_union",
+            &[
+                self.a.content_hash(),
+                self.b.content_hash(),
+                self.k.to_bits(),
+            ],
+        );
+    }
+}
+
+/// Carves `b` out of `a`.
+pub struct SmoothSubtract {
+    pub a: Arc<dyn Sdf>,
+    pub b: Arc<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SmoothSubtract {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+        if self.k <= 0. {
+            return d1.max(-d2);
+        }
+
+        let h = (0.5 - 0.5 * (d1 + d2) / self.k).clamp(0., 1.);
+        return lerp(d1, -d2, h) + self.k * h * (1. - h);
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "smooth_subtract",
+            &[
+                self.a.content_hash(),
+                self.b.content_hash(),
+                self.k.to_bits(),
+            ],
+        );
+    }
+}
+
+pub struct SmoothIntersect {
+    pub a: Arc<dyn Sdf>,
+    pub b: Arc<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SmoothIntersect {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+        if self.k <= 0. {
+            return d1.max(d2);
+        }
+
+        let h = (0.5 - 0.5 * (d2 - d1) / self.k).clamp(0., 1.);
+        return lerp(d2, d1, h) + self.k * h * (1. - h);
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "smooth_intersect",
+            &[
+                self.a.content_hash(),
+                self.b.content_hash(),
+                self.k.to_bits(),
+            ],
+        );
+    }
+}
+
+#[inline]
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    return a + (b - a) * t;
+}
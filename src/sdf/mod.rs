@@ -0,0 +1,130 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    hit::{Hit, Hittable},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    sampler,
+    vec3::{Point3, Vec3},
+};
+
+pub mod combinators;
+pub mod primitives;
+
+/// A signed distance function: negative inside, positive outside.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, p: &Point3) -> f64;
+
+    /// Hash of the parameters, see `Hittable::content_hash`.
+    fn content_hash(&self) -> u64;
+}
+
+/// Makes any [`Sdf`] hittable by sphere tracing along the ray.
+pub struct SdfObject {
+    pub sdf: Arc<dyn Sdf>,
+    pub material: Arc<dyn Material>,
+    pub max_steps: usize,
+    pub epsilon: f64,
+    /// Rays give up once they are this far from their origin. Without it every
+    /// miss into an open interval marches until `max_steps` runs out.
+    pub max_distance: f64,
+}
+
+/// A ray that runs out of steps this close to the surface counts as a hit,
+/// grazing rays creep along it and would otherwise show up as holes.
+const GRAZING_TOLERANCE: f64 = 10.;
+
+impl SdfObject {
+    pub fn new(sdf: Arc<dyn Sdf>, material: Arc<dyn Material>) -> Self {
+        return Self {
+            sdf,
+            material,
+            max_steps: 256,
+            epsilon: 1e-4,
+            max_distance: 1e3,
+        };
+    }
+
+    /// Gradient of the distance field by central differences.
+    fn normal(&self, p: &Point3) -> Vec3 {
+        let h = self.epsilon;
+        let dx = Vec3(h, 0., 0.);
+        let dy = Vec3(0., h, 0.);
+        let dz = Vec3(0., 0., h);
+
+        return Vec3(
+            self.sdf.distance(&(*p + dx)) - self.sdf.distance(&(*p - dx)),
+            self.sdf.distance(&(*p + dy)) - self.sdf.distance(&(*p - dy)),
+            self.sdf.distance(&(*p + dz)) - self.sdf.distance(&(*p - dz)),
+        )
+        .norm();
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+        let dir_len = r.direction.len();
+        let t_max = interval.max.min(self.max_distance / dir_len);
+        let mut t = interval.min;
+        let mut steps = 0;
+
+        // Rays spawned on the surface start inside the epsilon shell, step out of it first
+        let mut d = self.sdf.distance(&r.at(t));
+        while d.abs() < self.epsilon {
+            steps += 1;
+            if steps >= self.max_steps {
+                return false;
+            }
+            t += 2. * self.epsilon / dir_len;
+            d = self.sdf.distance(&r.at(t));
+        }
+
+        // March towards the boundary from whichever side we started on
+        let side = d.signum();
+        let mut d = side * d;
+        while d >= self.epsilon {
+            if steps >= self.max_steps {
+                if d < GRAZING_TOLERANCE * self.epsilon {
+                    break;
+                }
+                return false;
+            }
+
+            t += d / dir_len;
+            if t >= t_max {
+                return false;
+            }
+            d = side * self.sdf.distance(&r.at(t));
+            steps += 1;
+        }
+
+        if !interval.sourrounds(t) {
+            return false;
+        }
+
+        hit.t = t;
+        hit.p = r.at(t);
+        let outward_normal = self.normal(&hit.p);
+        hit.set_face_normal(r, &outward_normal);
+        hit.set_azimuthal_tangents(&outward_normal);
+        hit.material = Some(Arc::clone(&self.material));
+
+        return true;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "sdf_object",
+            &[
+                self.sdf.content_hash(),
+                self.material.content_hash(),
+                self.max_steps as u64,
+                self.epsilon.to_bits(),
+                self.max_distance.to_bits(),
+            ],
+        );
+    }
+}
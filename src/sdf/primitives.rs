@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+
+use crate::{
+    sampler,
+    vec3::{Point3, Vec3},
+};
+
+use super::Sdf;
+
+/// Axis aligned box with rounded edges. `half_extents` includes the rounding.
+pub struct RoundedBox {
+    pub center: Point3,
+    pub half_extents: Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for RoundedBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let p = *p - self.center;
+        let q = Vec3(p.0.abs(), p.1.abs(), p.2.abs()) - self.half_extents.sub_scalar(self.radius);
+        let outside = Vec3(q.0.max(0.), q.1.max(0.), q.2.max(0.)).len();
+        let inside = q.0.max(q.1).max(q.2).min(0.);
+        return outside + inside - self.radius;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "rounded_box",
+            &[
+                &self.center.to_bits()[..],
+                &self.half_extents.to_bits(),
+                &[self.radius.to_bits()],
+            ]
+            .concat(),
+        );
+    }
+}
+
+/// Line segment from `a` to `b` swept by a sphere.
+pub struct Capsule {
+    pub a: Point3,
+    pub b: Point3,
+    pub radius: f64,
+}
+
+impl Sdf for Capsule {
+    fn distance(&self, p: &Point3) -> f64 {
+        let pa = *p - self.a;
+        let ba = self.b - self.a;
+
+        // Both ends in the same place, that's just a sphere
+        let len_squared = ba.len_squared();
+        if len_squared < 1e-12 {
+            return pa.len() - self.radius;
+        }
+
+        let h = (pa.dot(&ba) / len_squared).clamp(0., 1.);
+        return (pa - ba.mul(h)).len() - self.radius;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "capsule",
+            &[
+                &self.a.to_bits()[..],
+                &self.b.to_bits(),
+                &[self.radius.to_bits()],
+            ]
+            .concat(),
+        );
+    }
+}
+
+/// Torus lying in the xz-plane around `center`.
+pub struct Torus {
+    pub center: Point3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Sdf for Torus {
+    fn distance(&self, p: &Point3) -> f64 {
+        let p = *p - self.center;
+        let ring = (p.0 * p.0 + p.2 * p.2).sqrt() - self.major_radius;
+        return (ring * ring + p.1 * p.1).sqrt() - self.minor_radius;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "torus",
+            &[
+                &self.center.to_bits()[..],
+                &[self.major_radius.to_bits(), self.minor_radius.to_bits()],
+            ]
+            .concat(),
+        );
+    }
+}
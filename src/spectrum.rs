@@ -0,0 +1,149 @@
+#![allow(dead_code)]
+
+//! Helpers for the spectral rendering mode. Every path carries `SAMPLES`
+//! wavelengths, the first one is the hero that decides wavelength dependent
+//! scattering, the others are spread evenly across the visible range.
+
+use std::sync::OnceLock;
+
+use crate::vec3::Color;
+
+pub const LAMBDA_MIN: f64 = 380.;
+pub const LAMBDA_MAX: f64 = 720.;
+pub const SAMPLES: usize = 4;
+
+/// Wavelengths in nanometers standing in for the red, green and blue channels
+/// outside of spectral rendering, each maps to exactly one channel in
+/// `rgb_to_spectral`.
+pub const RGB_WAVELENGTHS: [f64; 3] = [640., 540., 450.];
+
+pub type Wavelengths = [f64; SAMPLES];
+pub type SpectralSample = [f64; SAMPLES];
+
+/// Hero wavelength at `u` across the visible range, the rest rotated from it.
+pub fn sample_wavelengths(u: f64) -> Wavelengths {
+    let mut out = [0.; SAMPLES];
+    for (i, lambda) in out.iter_mut().enumerate() {
+        let u = (u + i as f64 / SAMPLES as f64).fract();
+        *lambda = LAMBDA_MIN + u * (LAMBDA_MAX - LAMBDA_MIN);
+    }
+    return out;
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    return t * t * (3. - 2. * t);
+}
+
+/// Cheap RGB to reflectance spectrum upsampling with three smooth basis
+/// functions that sum to one. White stays flat and nothing leaves `[0, 1]`,
+/// but a round trip doesn't reproduce saturated colors exactly.
+pub fn rgb_to_spectral(c: &Color, lambda: f64) -> f64 {
+    let blue = 1. - smoothstep(470., 510., lambda);
+    let red = smoothstep(570., 610., lambda);
+    let green = 1. - blue - red;
+    return c.0 * red + c.1 * green + c.2 * blue;
+}
+
+fn piecewise_gaussian(x: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+    let sigma = if x < mu { sigma_low } else { sigma_high };
+    let t = (x - mu) / sigma;
+    return (-0.5 * t * t).exp();
+}
+
+/// CIE 1931 color matching functions, multi-lobe fit by Wyman, Sloan and
+/// Shirley 2013, "Simple Analytic Approximations to the CIE XYZ Color Matching Functions".
+pub fn cie_xyz(lambda: f64) -> (f64, f64, f64) {
+    let x = 1.056 * piecewise_gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * piecewise_gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * piecewise_gaussian(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * piecewise_gaussian(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * piecewise_gaussian(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * piecewise_gaussian(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * piecewise_gaussian(lambda, 459.0, 26.0, 13.8);
+    return (x, y, z);
+}
+
+fn xyz_to_linear_srgb(x: f64, y: f64, z: f64) -> Color {
+    return Color::from_floats(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    );
+}
+
+/// Integral of the `y` matching function and the linear sRGB color of a flat
+/// spectrum, which gets balanced back to white.
+fn normalization() -> &'static (f64, Color) {
+    static NORMALIZATION: OnceLock<(f64, Color)> = OnceLock::new();
+    return NORMALIZATION.get_or_init(|| {
+        let (mut x, mut y, mut z) = (0., 0., 0.);
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            let (cx, cy, cz) = cie_xyz(lambda);
+            x += cx;
+            y += cy;
+            z += cz;
+            lambda += 1.;
+        }
+
+        let white = xyz_to_linear_srgb(x / y, 1., z / y);
+        return (y, white);
+    });
+}
+
+/// Monte Carlo estimate of a spectral sample's color, in linear sRGB.
+pub fn to_rgb(lambdas: &Wavelengths, values: &SpectralSample) -> Color {
+    let (y_integral, white) = normalization();
+    // Uniform wavelength pdf
+    let scale = (LAMBDA_MAX - LAMBDA_MIN) / (SAMPLES as f64 * y_integral);
+
+    let (mut x, mut y, mut z) = (0., 0., 0.);
+    for (lambda, value) in lambdas.iter().zip(values) {
+        let (cx, cy, cz) = cie_xyz(*lambda);
+        x += cx * value;
+        y += cy * value;
+        z += cz * value;
+    }
+
+    return xyz_to_linear_srgb(x * scale, y * scale, z * scale) / *white;
+}
+
+/// Wavelength dependent index of refraction. Wavelengths are in micrometers
+/// inside the formulas, like most published coefficients.
+#[derive(Debug, Clone, Copy)]
+pub enum Dispersion {
+    /// `n = a + b / λ²`
+    Cauchy { a: f64, b: f64 },
+    /// `n² = 1 + Σ b_i λ² / (λ² - c_i)`
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    pub fn bk7() -> Self {
+        return Self::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        };
+    }
+
+    pub fn diamond() -> Self {
+        return Self::Sellmeier {
+            b: [0.3306, 4.3356, 0.],
+            c: [0.030625, 0.011236, 0.],
+        };
+    }
+
+    /// Index of refraction at `lambda` nanometers.
+    pub fn ior(&self, lambda: f64) -> f64 {
+        let um = lambda / 1000.;
+        let um2 = um * um;
+        return match self {
+            Self::Cauchy { a, b } => a + b / um2,
+            Self::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c).map(|(b, c)| b * um2 / (um2 - c)).sum();
+                (1. + sum).sqrt()
+            }
+        };
+    }
+}
@@ -1,12 +1,15 @@
 #![allow(dead_code)]
 
+use core::f64;
 use std::sync::Arc;
 
 use crate::{
-    hit::Hit,
+    csg::{Solid, Span},
+    hit::{Hit, Hittable},
     interval::Interval,
     material::Material,
     ray::Ray,
+    sampler,
     vec3::{Point3, Vec3},
 };
 
@@ -25,7 +28,50 @@ impl Sphere {
         };
     }
 
-    pub fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+    /// Maps a point on the unit sphere to `u` around the y-axis starting at -x,
+    /// `v` from -y to +y.
+    fn uv(p: &Point3) -> (f64, f64) {
+        let theta = (-p.1).acos();
+        let phi = (-p.2).atan2(p.0) + f64::consts::PI;
+        return (phi / (2. * f64::consts::PI), theta / f64::consts::PI);
+    }
+
+    /// `dp/du` and `dp/dv` of the `uv` mapping at the outward normal `n`.
+    fn tangents(&self, n: &Vec3) -> (Vec3, Vec3) {
+        let cos_theta = -n.1;
+        let sin_theta = (1. - cos_theta * cos_theta).sqrt().max(1e-8);
+
+        let dpdu = Vec3(n.2, 0., -n.0).mul(2. * f64::consts::PI * self.radius);
+        let dpdv = Vec3(
+            cos_theta * n.0 / sin_theta,
+            sin_theta,
+            cos_theta * n.2 / sin_theta,
+        )
+        .mul(f64::consts::PI * self.radius);
+        return (dpdu, dpdv);
+    }
+
+    fn boundary(&self, r: &Ray, t: f64) -> Hit {
+        let p = r.at(t);
+        let normal = (p - self.center).div(self.radius);
+        let (u, v) = Self::uv(&normal);
+        let (dpdu, dpdv) = self.tangents(&normal);
+        return Hit {
+            p,
+            normal,
+            t,
+            material: Some(Arc::clone(&self.material)),
+            u,
+            v,
+            dpdu,
+            dpdv,
+            ..Default::default()
+        };
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
         let oc = self.center - r.origin;
         let a = r.direction.len_squared();
         let h = r.direction.dot(&oc);
@@ -51,8 +97,41 @@ impl Sphere {
         hit.p = r.at(hit.t);
         let outward_normal = (hit.p - self.center).div(self.radius);
         hit.set_face_normal(r, &outward_normal);
+        (hit.u, hit.v) = Self::uv(&outward_normal);
+        (hit.dpdu, hit.dpdv) = self.tangents(&outward_normal);
         hit.material = Some(Arc::clone(&self.material));
 
         return true;
     }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "sphere",
+            &[
+                &self.center.to_bits()[..],
+                &[self.radius.to_bits(), self.material.content_hash()],
+            ]
+            .concat(),
+        );
+    }
+}
+
+impl Solid for Sphere {
+    fn spans(&self, r: &Ray) -> Vec<Span> {
+        let oc = self.center - r.origin;
+        let a = r.direction.len_squared();
+        let h = r.direction.dot(&oc);
+        let c = oc.len_squared() - self.radius * self.radius;
+        let disc: f64 = h * h - a * c;
+
+        if disc <= 0. {
+            return vec![];
+        }
+
+        let disc_sqrt = disc.sqrt();
+        return vec![Span {
+            enter: self.boundary(r, (h - disc_sqrt) / a),
+            exit: self.boundary(r, (h + disc_sqrt) / a),
+        }];
+    }
 }
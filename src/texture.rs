@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{hit::Hit, image::Image, sampler, vec3::Color};
+
+pub trait Texture: Send + Sync {
+    fn value(&self, hit: &Hit) -> Color;
+
+    /// Hash of the parameters, see `Hittable::content_hash`.
+    fn content_hash(&self) -> u64;
+}
+
+/// A plain color is a texture that's the same everywhere.
+impl Texture for Color {
+    fn value(&self, _hit: &Hit) -> Color {
+        return *self;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named("color", &self.to_bits());
+    }
+}
+
+/// Looks up the hit's `(u, v)` in an image, e.g. masks loaded with `Image::load_ppm`.
+pub struct ImageTexture {
+    pub image: Image,
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, hit: &Hit) -> Color {
+        return self.image.sample_uv(hit.u, hit.v);
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "image_texture",
+            &[
+                &[self.image.width as u64, self.image.height as u64][..],
+                &self
+                    .image
+                    .pixels
+                    .iter()
+                    .flat_map(|c| c.to_bits())
+                    .collect::<Vec<_>>(),
+            ]
+            .concat(),
+        );
+    }
+}
+
+/// Multiplies another texture by a constant color.
+pub struct Scaled {
+    pub texture: Arc<dyn Texture>,
+    pub factor: Color,
+}
+
+impl Texture for Scaled {
+    fn value(&self, hit: &Hit) -> Color {
+        return self.texture.value(hit) * self.factor;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "scaled",
+            &[&[self.texture.content_hash()][..], &self.factor.to_bits()].concat(),
+        );
+    }
+}
+
+/// Interpolated per-vertex color of meshes, `fallback` for anything without one.
+pub struct VertexColor {
+    pub fallback: Color,
+}
+
+impl Texture for VertexColor {
+    fn value(&self, hit: &Hit) -> Color {
+        return hit.vertex_color.unwrap_or(self.fallback);
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named("vertex_color", &self.fallback.to_bits());
+    }
+}
@@ -0,0 +1,134 @@
+#![allow(dead_code)]
+
+/// Order in which workers pick up tiles. Results don't depend on it, only what
+/// shows up first and how well neighbouring work shares caches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileOrder {
+    /// Row by row from the top left
+    Scanline,
+    /// Along a Hilbert curve over the enclosing power of two grid. Consecutive
+    /// tiles touch on square grids, elsewhere the curve jumps where it leaves
+    /// the image.
+    Hilbert,
+    /// Rings around the center, where the subject usually is
+    Spiral,
+}
+
+/// Pixel bounds of a tile, `x1` and `y1` are exclusive.
+#[derive(Clone, Copy, Debug)]
+pub struct Tile {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Tile {
+    pub fn width(&self) -> usize {
+        return self.x1 - self.x0;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.y1 - self.y0;
+    }
+}
+
+/// Covers the image with `size` by `size` tiles, smaller at the right and
+/// bottom edges, in the given order.
+pub fn tiles(width: usize, height: usize, size: usize, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let nx = width.div_ceil(size);
+    let ny = height.div_ceil(size);
+
+    let grid: Vec<(usize, usize)> = match order {
+        TileOrder::Scanline => (0..ny)
+            .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+            .collect(),
+        TileOrder::Hilbert => {
+            let n = nx.max(ny).next_power_of_two();
+            (0..n * n)
+                .map(|d| hilbert(n, d))
+                .filter(|(tx, ty)| *tx < nx && *ty < ny)
+                .collect()
+        }
+        TileOrder::Spiral => {
+            let (cx, cy) = ((nx as f64 - 1.) / 2., (ny as f64 - 1.) / 2.);
+            let key = |(tx, ty): (usize, usize)| {
+                let (dx, dy) = (tx as f64 - cx, ty as f64 - cy);
+                let ring = dx.abs().max(dy.abs()).round();
+                (ring, dy.atan2(dx))
+            };
+
+            let mut grid: Vec<(usize, usize)> = (0..ny)
+                .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+                .collect();
+            grid.sort_by(|a, b| {
+                let (ka, kb) = (key(*a), key(*b));
+                ka.0.total_cmp(&kb.0).then(ka.1.total_cmp(&kb.1))
+            });
+            grid
+        }
+    };
+
+    return grid
+        .into_iter()
+        .map(|(tx, ty)| Tile {
+            x0: tx * size,
+            y0: ty * size,
+            x1: ((tx + 1) * size).min(width),
+            y1: ((ty + 1) * size).min(height),
+        })
+        .collect();
+}
+
+/// Position `d` along the Hilbert curve filling an `n` by `n` grid, `n` a power of two.
+fn hilbert(n: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    return (x, y);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_order_covers_each_pixel_once() {
+        for order in [TileOrder::Scanline, TileOrder::Hilbert, TileOrder::Spiral] {
+            for (width, height, size) in [(37, 19, 8), (19, 37, 8), (64, 64, 16), (5, 3, 7)] {
+                let mut covered = vec![0; width * height];
+                for tile in tiles(width, height, size, order) {
+                    for y in tile.y0..tile.y1 {
+                        for x in tile.x0..tile.x1 {
+                            covered[y * width + x] += 1;
+                        }
+                    }
+                }
+                assert!(
+                    covered.iter().all(|c| *c == 1),
+                    "{:?} on {}x{} with {} pixel tiles",
+                    order,
+                    width,
+                    height,
+                    size
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{interval::Interval, ray::Ray, vec3::Point3};
+
+/// Möller–Trumbore ray/triangle intersection. Returns `t` and the barycentric
+/// weights of `p1` and `p2`.
+pub fn intersect(
+    r: &Ray,
+    interval: &Interval,
+    p0: &Point3,
+    p1: &Point3,
+    p2: &Point3,
+) -> Option<(f64, f64, f64)> {
+    let e1 = *p1 - *p0;
+    let e2 = *p2 - *p0;
+    let pvec = r.direction.cross(&e2);
+    let det = e1.dot(&pvec);
+
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1. / det;
+
+    let tvec = r.origin - *p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0. ..=1.).contains(&b1) {
+        return None;
+    }
+
+    let qvec = tvec.cross(&e1);
+    let b2 = r.direction.dot(&qvec) * inv_det;
+    if b2 < 0. || b1 + b2 > 1. {
+        return None;
+    }
+
+    let t = e2.dot(&qvec) * inv_det;
+    if !interval.sourrounds(t) {
+        return None;
+    }
+
+    return Some((t, b1, b2));
+}
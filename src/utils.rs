@@ -1,18 +1,25 @@
-use std::sync::{Arc, Mutex};
-
 use crate::{interval::Interval, vec3::Color};
 
-pub fn write_color(out: Arc<Mutex<Vec<String>>>, col: &Color, x: usize, y: usize, width: usize) {
+/// Appends `col` as a gamma 2 PPM pixel line.
+pub fn write_color(out: &mut String, col: &Color) {
     let intensity = Interval::new(0., 0.99999);
 
-    let line = format!(
-        "{} {} {}",
+    out.push_str(&format!(
+        "{} {} {}\n",
         (256f64 * intensity.clamp(col.0.to_gamma())) as u8,
         (256f64 * intensity.clamp(col.1.to_gamma())) as u8,
         (256f64 * intensity.clamp(col.2.to_gamma())) as u8,
-    );
-    let mut handle = out.lock().unwrap();
-    handle[width * y + x] = line;
+    ));
+}
+
+/// Relative luminance of a linear Rec. 709 color.
+pub fn luminance(c: &Color) -> f64 {
+    return 0.2126 * c.0 + 0.7152 * c.1 + 0.0722 * c.2;
+}
+
+/// Reseeds the calling thread's random numbers, other threads keep theirs.
+pub fn seed_rng(seed: u64) {
+    fastrand::seed(seed);
 }
 
 #[inline]
@@ -77,6 +77,11 @@ impl Vec3 {
         return self.0.abs() < t && self.1.abs() < t && self.2.abs() < t;
     }
 
+    /// Raw bits of the components, for hashing.
+    pub fn to_bits(self) -> [u64; 3] {
+        return [self.0.to_bits(), self.1.to_bits(), self.2.to_bits()];
+    }
+
     pub fn from_tup(base: (f64, f64, f64)) -> Vec3 {
         return Vec3(base.0, base.1, base.2);
     }
@@ -132,7 +137,7 @@ impl Vec3 {
     pub fn refract(uv: &Vec3, n: &Vec3, refraction_index: f64) -> Vec3 {
         let cos_theta = uv.inv().dot(n).min(1.);
         let out_perp = *uv + n.mul(cos_theta);
-        let out_perp = (&out_perp).mul(refraction_index);
+        let out_perp = Vec3::mul(&out_perp, refraction_index);
         let out_par = n.mul(-(1. - out_perp.len_squared()).abs().sqrt());
         return out_perp + out_par;
     }
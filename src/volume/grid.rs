@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+
+use std::{fs, io, path::Path};
+
+use crate::vec3::Point3;
+
+/// Density values on a regular 3D grid, x varies fastest.
+pub struct VoxelGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub data: Vec<f32>,
+    max: f64,
+}
+
+impl VoxelGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f32>) -> Self {
+        assert_eq!(
+            nx * ny * nz,
+            data.len(),
+            "Voxel data doesn't match the grid dimensions"
+        );
+
+        let max = data.iter().fold(0f32, |acc, d| acc.max(*d)) as f64;
+        return Self {
+            nx,
+            ny,
+            nz,
+            data,
+            max,
+        };
+    }
+
+    /// Loads a raw grid file. The layout is three little endian `u32`
+    /// dimensions (x, y, z) followed by `x * y * z` little endian `f32` densities.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        if bytes.len() < 12 {
+            return Err(invalid("Voxel file is missing its header"));
+        }
+
+        let read_u32 = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) as usize;
+        let (nx, ny, nz) = (read_u32(0), read_u32(4), read_u32(8));
+
+        let body = &bytes[12..];
+        let size = nx
+            .checked_mul(ny)
+            .and_then(|n| n.checked_mul(nz))
+            .and_then(|n| n.checked_mul(4));
+        if size != Some(body.len()) {
+            return Err(invalid("Voxel data doesn't match the grid dimensions"));
+        }
+
+        let data = body
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+
+        return Ok(Self::new(nx, ny, nz, data));
+    }
+
+    pub fn max_density(&self) -> f64 {
+        return self.max;
+    }
+
+    fn at(&self, x: isize, y: isize, z: isize) -> f64 {
+        let x = x.clamp(0, self.nx as isize - 1) as usize;
+        let y = y.clamp(0, self.ny as isize - 1) as usize;
+        let z = z.clamp(0, self.nz as isize - 1) as usize;
+        return self.data[(z * self.ny + y) * self.nx + x] as f64;
+    }
+
+    /// Trilinearly interpolated density at `p`, given in grid local `[0, 1]^3` coordinates.
+    pub fn sample(&self, p: Point3) -> f64 {
+        // Voxel values sit in the cell centers
+        let gx = p.0 * self.nx as f64 - 0.5;
+        let gy = p.1 * self.ny as f64 - 0.5;
+        let gz = p.2 * self.nz as f64 - 0.5;
+
+        let (x0, y0, z0) = (gx.floor(), gy.floor(), gz.floor());
+        let (fx, fy, fz) = (gx - x0, gy - y0, gz - z0);
+        let (x0, y0, z0) = (x0 as isize, y0 as isize, z0 as isize);
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+        let c00 = lerp(self.at(x0, y0, z0), self.at(x0 + 1, y0, z0), fx);
+        let c10 = lerp(self.at(x0, y0 + 1, z0), self.at(x0 + 1, y0 + 1, z0), fx);
+        let c01 = lerp(self.at(x0, y0, z0 + 1), self.at(x0 + 1, y0, z0 + 1), fx);
+        let c11 = lerp(
+            self.at(x0, y0 + 1, z0 + 1),
+            self.at(x0 + 1, y0 + 1, z0 + 1),
+            fx,
+        );
+
+        return lerp(lerp(c00, c10, fy), lerp(c01, c11, fy), fz);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_bytes(name: &str, bytes: &[u8]) -> io::Result<VoxelGrid> {
+        let path = std::env::temp_dir().join(format!("rtiow-{}-{}.vox", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        let grid = VoxelGrid::load(&path);
+        fs::remove_file(&path).unwrap();
+        return grid;
+    }
+
+    fn file(dims: [u32; 3], densities: &[f32]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = dims.iter().flat_map(|d| d.to_le_bytes()).collect();
+        bytes.extend(densities.iter().flat_map(|d| d.to_le_bytes()));
+        return bytes;
+    }
+
+    #[test]
+    fn loads_dimensions_and_densities() {
+        let grid = load_bytes("valid", &file([2, 1, 1], &[0.25, 2.])).unwrap();
+        assert_eq!((grid.nx, grid.ny, grid.nz), (2, 1, 1));
+        assert_eq!(grid.data, vec![0.25, 2.]);
+        assert_eq!(grid.max_density(), 2.);
+    }
+
+    #[test]
+    fn truncated_file_is_invalid() {
+        let bytes = file([2, 2, 1], &[1., 1., 1., 1.]);
+        let err = load_bytes("truncated", &bytes[..bytes.len() - 3])
+            .err()
+            .expect("truncated grid loads");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = load_bytes("no-header", &bytes[..8])
+            .err()
+            .expect("headerless grid loads");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn overflowing_header_is_invalid() {
+        let err = load_bytes("overflow", &file([u32::MAX; 3], &[1.]))
+            .err()
+            .expect("overflowing grid loads");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,109 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hit::{Hit, Hittable},
+    interval::Interval,
+    material::{isotropic::Isotropic, Material},
+    ray::Ray,
+    sampler, utils,
+    vec3::{Color, Point3, Vec3},
+};
+
+use super::grid::VoxelGrid;
+
+/// A participating medium whose density is looked up in a voxel grid
+/// stretched over `bounds`.
+pub struct HeterogeneousMedium {
+    pub bounds: Aabb,
+    pub grid: VoxelGrid,
+    pub density_scale: f64,
+    pub phase_function: Arc<dyn Material>,
+    majorant: f64,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(bounds: Aabb, grid: VoxelGrid, density_scale: f64, albedo: Color) -> Self {
+        let majorant = grid.max_density() * density_scale;
+        return Self {
+            bounds,
+            grid,
+            density_scale,
+            phase_function: Arc::new(Isotropic { albedo }),
+            majorant,
+        };
+    }
+
+    fn density(&self, p: Point3) -> f64 {
+        let local = (p - self.bounds.min) / self.bounds.extent();
+        return self.grid.sample(local) * self.density_scale;
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    // Delta tracking: sample tentative collisions against the majorant and
+    // accept each one with probability `density / majorant`.
+    fn hit(&self, r: &Ray, interval: &Interval, hit: &mut Hit) -> bool {
+        if self.majorant <= 0. {
+            return false;
+        }
+
+        let Some((t_enter, t_exit)) = self.bounds.hit_range(r, interval) else {
+            return false;
+        };
+
+        let ray_len = r.direction.len();
+        let mut t = t_enter;
+
+        loop {
+            t -= (1. - utils::rand_float()).ln() / (self.majorant * ray_len);
+            if t >= t_exit {
+                return false;
+            }
+
+            if utils::rand_float() * self.majorant < self.density(r.at(t)) {
+                break;
+            }
+        }
+
+        hit.t = t;
+        hit.p = r.at(t);
+        // Arbitrary, media have no surface
+        let normal = Vec3::from_floats(1., 0., 0.);
+        hit.normal = normal;
+        hit.shading_normal = normal;
+        hit.front_face = true;
+        hit.set_azimuthal_tangents(&normal);
+        hit.material = Some(Arc::clone(&self.phase_function));
+
+        return true;
+    }
+
+    fn content_hash(&self) -> u64 {
+        return sampler::hash_named(
+            "heterogeneous_medium",
+            &[
+                &self.bounds.min.to_bits()[..],
+                &self.bounds.max.to_bits(),
+                &[
+                    self.grid.nx as u64,
+                    self.grid.ny as u64,
+                    self.grid.nz as u64,
+                ],
+                &self
+                    .grid
+                    .data
+                    .iter()
+                    .map(|d| d.to_bits() as u64)
+                    .collect::<Vec<_>>(),
+                &[
+                    self.density_scale.to_bits(),
+                    self.phase_function.content_hash(),
+                ],
+            ]
+            .concat(),
+        );
+    }
+}
@@ -1,6 +1,13 @@
-use crate::{hit::Hit, interval::Interval, ray::Ray, sphere::Sphere};
+use std::sync::Arc;
 
-pub type World = Vec<Sphere>;
+use crate::{
+    hit::{Hit, Hittable},
+    interval::Interval,
+    ray::Ray,
+    sampler,
+};
+
+pub type World = Vec<Arc<dyn Hittable>>;
 
 pub trait AnyHit {
     fn any_hit(&self, r: &Ray, interval: Interval) -> Option<Hit>;
@@ -8,16 +15,26 @@ pub trait AnyHit {
 
 impl AnyHit for World {
     fn any_hit(&self, r: &Ray, mut interval: Interval) -> Option<Hit> {
-        let mut hit = Hit::default();
-        let mut has_hit = false;
+        // Primitives only fill the fields they know about, so every candidate
+        // starts from a blank hit. Otherwise a closer sphere would inherit the
+        // UVs or vertex color of a mesh tested before it.
+        let blank = Hit::default();
+        let mut closest = None;
 
         for item in self.iter() {
+            let mut hit = blank.clone();
             if item.hit(r, &interval, &mut hit) {
-                has_hit = true;
                 interval.max = hit.t;
+                closest = Some(hit);
             }
         }
 
-        return if has_hit { Some(hit) } else { None };
+        return closest;
     }
 }
+
+/// Combined `Hittable::content_hash` of everything in the world, in order.
+pub fn scene_hash(world: &World) -> u64 {
+    let items: Vec<u64> = world.iter().map(|item| item.content_hash()).collect();
+    return sampler::hash(&items);
+}